@@ -0,0 +1,63 @@
+use super::error::{Error, Result};
+
+use serenity::model::channel::Attachment;
+
+// Discord accepts much larger files, but no one writes source files this large
+pub const MAX_FILE_BYTES: u64 = 64 * 1024;
+pub const MAX_FILES: usize = 8;
+
+const STDIN_FILENAME: &str = "stdin.txt";
+
+#[derive(Debug, Clone)]
+pub struct AttachedFile {
+    pub filename: String,
+    pub text: String,
+}
+
+impl AttachedFile {
+    pub fn is_stdin(&self) -> bool {
+        self.filename.eq_ignore_ascii_case(STDIN_FILENAME)
+    }
+
+    pub fn extension(&self) -> Option<&str> {
+        extension(&self.filename)
+    }
+}
+
+pub fn is_stdin(attachment: &Attachment) -> bool {
+    attachment.filename.eq_ignore_ascii_case(STDIN_FILENAME)
+}
+
+pub fn extension(filename: &str) -> Option<&str> {
+    match filename.rsplitn(2, '.').collect::<Vec<_>>()[..] {
+        [ext, name] if !name.is_empty() && !ext.is_empty() => Some(ext),
+        _ => None,
+    }
+}
+
+// Downloads attached files as text. Images are skipped, as they cannot be a code anyway.
+pub fn download(attachments: &[Attachment]) -> Result<Vec<AttachedFile>> {
+    let attachments: Vec<_> = attachments
+        .iter()
+        .filter(|a| a.dimensions().is_none())
+        .collect();
+
+    if attachments.len() > MAX_FILES {
+        return Err(Error::TooManyAttachments(MAX_FILES));
+    }
+    // check all sizes before downloading anything
+    if let Some(a) = attachments.iter().find(|a| a.size > MAX_FILE_BYTES) {
+        return Err(Error::TooLargeAttachment(a.filename.clone()));
+    }
+
+    let mut files = Vec::new();
+    for attachment in attachments {
+        let data = attachment.download()?;
+        let text = String::from_utf8(data).map_err(|e| e.utf8_error())?;
+        files.push(AttachedFile {
+            filename: attachment.filename.clone(),
+            text,
+        });
+    }
+    Ok(files)
+}
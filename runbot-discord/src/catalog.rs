@@ -0,0 +1,474 @@
+use std::fmt;
+
+use super::command::Kind;
+use super::locale::Locale;
+
+type Arg<'a> = &'a dyn fmt::Display;
+
+// Every message shown to users. The matches below are exhaustive, so a message
+// cannot be added without translating it into every locale.
+#[derive(Clone, Copy)]
+pub enum Text<'a> {
+    HelpHeader {
+        prefix: Arg<'a>,
+        run_reaction: Arg<'a>,
+    },
+    Description(Kind),
+    Aliases(Arg<'a>),
+    RequiredPermission(Arg<'a>),
+    SlashLanguage,
+    SlashCompiler,
+    SlashOptions,
+    SlashScope,
+    CodeModalTitle,
+    CodeModalCode,
+    CodeModalStdin,
+    NoAuditEntries,
+    NoChanges,
+    DryRun,
+    InferredLanguage(Arg<'a>),
+    OutputAttached,
+    ExitedWithStatus(Arg<'a>),
+    CompilationFailed(Arg<'a>),
+    ExitedWithSignal(Arg<'a>),
+    TimedOut,
+    ExitedWithUnknownStatus(Arg<'a>),
+    ExitedWithoutStatus,
+    UnknownLanguageName(Arg<'a>),
+    UnknownCompilerName(Arg<'a>),
+    UnknownCompilerSpec(Arg<'a>),
+    UnmappedLanguage(Arg<'a>),
+    NoCompilerSpecified,
+    RemapMismatch {
+        compiler: Arg<'a>,
+        language: Arg<'a>,
+    },
+    UnsupportedDocumentVersion(Arg<'a>),
+    InvalidDocumentValue {
+        key: Arg<'a>,
+        value: Arg<'a>,
+    },
+    AccessDenied(Arg<'a>),
+    RateLimited {
+        secs: Arg<'a>,
+    },
+    QuotaExceeded(Arg<'a>),
+    TooManyInFlight,
+    InvalidChannelID(Arg<'a>),
+    InvalidSettingFile(Arg<'a>),
+    InvalidCodeInput,
+    MalformedArguments,
+    InvalidArguments {
+        usages: Arg<'a>,
+    },
+    UnknownCommand(Arg<'a>),
+    InvalidValue {
+        value: Arg<'a>,
+        expected: Arg<'a>,
+    },
+    GuildOnly(Arg<'a>),
+    CommandIsMissing,
+    PermissionDenied(Arg<'a>),
+    UnknownPermission(Arg<'a>),
+    UnknownOutputPolicy(Arg<'a>),
+    UnknownRenderStyle(Arg<'a>),
+    UnknownLocale(Arg<'a>),
+    InvalidNumber(Arg<'a>),
+    InvalidMention(Arg<'a>),
+    TooManyAttachments(Arg<'a>),
+    TooLargeAttachment {
+        name: Arg<'a>,
+        max_kib: Arg<'a>,
+    },
+    Encoding,
+    InvalidPrefix {
+        prefix: Arg<'a>,
+        max_length: Arg<'a>,
+    },
+    TooLongCommandline(Arg<'a>),
+    UnknownError,
+}
+
+pub struct Localized<'a>(pub Text<'a>, pub Locale);
+
+impl fmt::Display for Localized<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.1 {
+            Locale::Ja => ja(&self.0, f),
+            Locale::En => en(&self.0, f),
+        }
+    }
+}
+
+pub fn localize(text: Text, locale: Locale) -> String {
+    Localized(text, locale).to_string()
+}
+
+fn ja(text: &Text, f: &mut fmt::Formatter) -> fmt::Result {
+    match text {
+        Text::HelpHeader {
+            prefix,
+            run_reaction,
+        } => write!(
+            f,
+            "
+`{prefix}` のあとに `global` をつけると全チャンネルに対して設定ができるよ
+`{prefix}` のあとに `my` をつけると自分だけの設定ができるよ (チャンネルの設定より優先されるよ)
+`{prefix}` の代わりにメンションしてもいいよ、`run` は `r`、`list` は `ls` みたいに短くも書けるよ
+コードブロックのあるメッセージに {run_reaction} でリアクションすると実行できるよ
+コードのあるメッセージに返信して `run` すると、そのコードを実行するよ
+ファイルを添付して実行もできるよ、`stdin.txt` は標準入力になるよ
+実行結果に 🗑️ でリアクションすると消せるよ
+`/run` や `/list` などのスラッシュコマンドも使えるよ
+DM でも実行できるよ、DM での設定はすべて自分だけのものになるよ
+`{prefix} help <コマンド>` でコマンドごとの使い方を表示するよ、設定の多くは `default` で元に戻せるよ
+",
+            prefix = prefix,
+            run_reaction = run_reaction
+        ),
+        Text::Description(kind) => match kind {
+            Kind::Help => write!(f, "これ"),
+            Kind::ShowSetting => write!(f, "設定表示"),
+            Kind::Auto => write!(f, "自動実行設定"),
+            Kind::NoAuto => write!(f, "自動実行解除"),
+            Kind::AutoSave => write!(f, "自動実行時に保存する"),
+            Kind::NoAutoSave => write!(f, "自動実行時に保存しない"),
+            Kind::Remap => write!(f, "言語名とコンパイラの紐付けを上書き"),
+            Kind::Options => write!(f, "コンパイラに渡すオプションを設定"),
+            Kind::Reset => write!(f, "自分だけの設定を全部消す"),
+            Kind::Audit => write!(f, "設定の変更履歴を表示"),
+            Kind::LogChannel => write!(f, "設定の変更履歴を流すチャンネルを設定"),
+            Kind::NoLogChannel => write!(f, "設定の変更履歴を流すチャンネルを解除"),
+            Kind::Permission => write!(f, "コマンドに必要な権限を設定"),
+            Kind::Access => write!(f, "実行できるロールやユーザーを設定"),
+            Kind::Quota => write!(f, "1日あたりの実行回数やコードの量を制限 (`none` で解除)"),
+            Kind::Usage => write!(f, "今日の使用量を表示"),
+            Kind::OutputPolicy => write!(
+                f,
+                "実行結果のメンションや招待リンクの扱い (`strict`、`mentions`、`off`)"
+            ),
+            Kind::OutputLimit => write!(f, "実行結果をそのまま表示する行数やバイト数"),
+            Kind::RenderStyle => write!(f, "実行結果の表示方法 (`embed`、`text`)"),
+            Kind::Prefix => write!(f, "コマンドのプレフィックスを変更"),
+            Kind::Locale => write!(f, "メッセージの言語を設定 (`ja`、`en`)"),
+            Kind::HideWarnings => write!(f, "実行できたときはコンパイラの警告を表示しない"),
+            Kind::ShowWarnings => write!(f, "コンパイラの警告をいつも表示する"),
+            Kind::Export => write!(f, "全チャンネルの設定をファイルに書き出す"),
+            Kind::Import => write!(f, "書き出した設定をファイルかコードブロックから読み込む (`--dry-run` で変更点だけ表示)"),
+            Kind::ListLanguages => write!(f, "言語を一覧"),
+            Kind::List => write!(f, "言語に対応するコンパイラを一覧"),
+            Kind::Run => write!(f, "実行"),
+            Kind::RunSave => write!(f, "実行して保存"),
+        },
+        Text::Aliases(a) => write!(f, "別名: {}", a),
+        Text::RequiredPermission(p) => write!(f, "必要な権限: `{}`", p),
+        Text::SlashLanguage => write!(f, "言語"),
+        Text::SlashCompiler => write!(f, "コンパイラ"),
+        Text::SlashOptions => write!(f, "コンパイラに渡すオプション"),
+        Text::SlashScope => write!(f, "設定の範囲 (省略するとこのチャンネル)"),
+        Text::CodeModalTitle => write!(f, "コードを貼ってね"),
+        Text::CodeModalCode => write!(f, "コード"),
+        Text::CodeModalStdin => write!(f, "標準入力"),
+        Text::NoAuditEntries => write!(f, "変更履歴はないよ"),
+        Text::NoChanges => write!(f, "変更はないよ"),
+        Text::DryRun => write!(f, "(お試しなので反映してないよ)"),
+        Text::InferredLanguage(l) => write!(f, "{} だと思って実行したよ", l),
+        Text::OutputAttached => write!(f, "出力が長いので全文は添付したよ"),
+        Text::ExitedWithStatus(s) => write!(f, "終了コード {} で終了したよ", s),
+        Text::CompilationFailed(s) => write!(f, "コンパイルに失敗したよ (終了コード {})", s),
+        Text::ExitedWithSignal(s) => write!(f, "シグナル {} で終了したよ", s),
+        Text::TimedOut => write!(f, "時間切れだよ"),
+        Text::ExitedWithUnknownStatus(s) => write!(f, "よくわからない状態 {} で終了したよ", s),
+        Text::ExitedWithoutStatus => write!(f, "終了コードなしで終了したよ"),
+        Text::UnknownLanguageName(name) => write!(f, "`{}` ？うーん...", name),
+        Text::UnknownCompilerName(name) => write!(f, "`{}` っ て 何 ？ 笑", name),
+        Text::UnknownCompilerSpec(name) => write!(
+            f,
+            "`{}` とはなんですか？普通、`{}` とはならないとおもうのですが...",
+            name, name
+        ),
+        Text::UnmappedLanguage(name) => {
+            write!(f, "`{}` に対応するコンパイラが決まっていない", name)
+        }
+        Text::NoCompilerSpecified => write!(f, "どのコンパイラを使えばいいかわかんないよ〜"),
+        Text::RemapMismatch { compiler, language } => {
+            write!(
+                f,
+                "や、`{}` は `{}` でコンパイルできないよ",
+                language, compiler
+            )
+        }
+        Text::UnsupportedDocumentVersion(v) => {
+            write!(f, "バージョン {} の設定ファイルは読めないよ", v)
+        }
+        Text::InvalidDocumentValue { key, value } => {
+            write!(f, "`{}` に `{}` は設定できないよ", key, value)
+        }
+        Text::AccessDenied(t) => {
+            write!(f, "このサーバーでは `{}` を使わせてもらえないみたい", t)
+        }
+        Text::RateLimited { secs } => {
+            write!(f, "ちょっと落ち着いて！{}秒後にもう一度試してね", secs)
+        }
+        Text::QuotaExceeded(k) => write!(f, "今日の `{}` の上限に達しちゃった、また明日ね", k),
+        Text::TooManyInFlight => {
+            write!(f, "いま混み合ってるよ、少し待ってからもう一度試してね")
+        }
+        Text::InvalidChannelID(id) => write!(f, "`{}` はチャンネルIDじゃないよ", id),
+        Text::InvalidSettingFile(e) => write!(f, "設定ファイルがおかしいよ: {}", e),
+        Text::InvalidCodeInput => write!(f, "コードの入力がおかしいよ"),
+        Text::MalformedArguments => write!(f, "ちょっと、いたずらしないでください"),
+        Text::InvalidArguments { usages } => {
+            write!(f, "引数がおかしいよ、使い方はこうだよ\n{}", usages)
+        }
+        Text::UnknownCommand(c) => write!(f, "`{}`、完全に理解した", c),
+        Text::InvalidValue { value, expected } => {
+            write!(f, "`{}` は使えないよ、{} のどれかにしてね", value, expected)
+        }
+        Text::GuildOnly(c) => write!(f, "`{}` はサーバーでしか使えないよ", c),
+        Text::CommandIsMissing => write!(f, "？"),
+        Text::PermissionDenied(p) => write!(f, "それをするには `{}` 権限が必要だよ", p),
+        Text::UnknownPermission(p) => write!(f, "`{}` という権限は知らないなあ", p),
+        Text::UnknownOutputPolicy(p) => write!(f, "`{}` という設定は知らないなあ", p),
+        Text::UnknownRenderStyle(s) => write!(f, "`{}` という表示方法は知らないなあ", s),
+        Text::UnknownLocale(l) => write!(f, "`{}` という言語は話せないなあ", l),
+        Text::InvalidNumber(n) => write!(f, "`{}` は数字じゃないよ", n),
+        Text::InvalidMention(m) => write!(f, "`{}` はロールかユーザーのメンションにしてね", m),
+        Text::TooManyAttachments(n) => write!(f, "添付ファイルは{}個までにしてね", n),
+        Text::TooLargeAttachment { name, max_kib } => {
+            write!(f, "`{}` は大きすぎるよ ({}KiBまで)", name, max_kib)
+        }
+        Text::Encoding => write!(f, "添付ファイルはUTF-8で書いてね"),
+        Text::InvalidPrefix { prefix, max_length } => write!(
+            f,
+            "`{}` はプレフィックスにできないよ (空白なしの{}文字以内にしてね)",
+            prefix, max_length
+        ),
+        Text::TooLongCommandline(n) => write!(f, "コマンドは{}文字までにしてね", n),
+        Text::UnknownError => write!(f, "ごめん"),
+    }
+}
+
+fn en(text: &Text, f: &mut fmt::Formatter) -> fmt::Result {
+    match text {
+        Text::HelpHeader {
+            prefix,
+            run_reaction,
+        } => write!(
+            f,
+            "
+Put `global` after `{prefix}` to configure all the channels
+Put `my` after `{prefix}` to configure just for yourself (which takes precedence over the channel)
+You can mention me instead of `{prefix}`, and shorten `run` to `r`, `list` to `ls` and so on
+React with {run_reaction} to a message with a code block to run it
+Reply `run` to a message with code to run that code
+Attached files can be run too, and `stdin.txt` becomes the standard input
+React with 🗑️ to a result to delete it
+Slash commands such as `/run` and `/list` are also available
+You can run code in direct messages too, where every setting is just for yourself
+`{prefix} help <command>` shows the usage of each command, and most settings are reset with `default`
+",
+            prefix = prefix,
+            run_reaction = run_reaction
+        ),
+        Text::Description(kind) => match kind {
+            Kind::Help => write!(f, "this"),
+            Kind::ShowSetting => write!(f, "show the settings"),
+            Kind::Auto => write!(f, "run code automatically"),
+            Kind::NoAuto => write!(f, "stop running code automatically"),
+            Kind::AutoSave => write!(f, "save code run automatically"),
+            Kind::NoAutoSave => write!(f, "do not save code run automatically"),
+            Kind::Remap => write!(f, "override the compiler used for a language"),
+            Kind::Options => write!(f, "set options passed to a compiler"),
+            Kind::Reset => write!(f, "clear all of your own settings"),
+            Kind::Audit => write!(f, "show the history of setting changes"),
+            Kind::LogChannel => write!(f, "set the channel to post setting changes to"),
+            Kind::NoLogChannel => write!(f, "stop posting setting changes"),
+            Kind::Permission => write!(f, "set the permission required for a command"),
+            Kind::Access => write!(f, "set roles and users allowed to run code"),
+            Kind::Quota => write!(f, "limit executions or code size per day (`none` to unset)"),
+            Kind::Usage => write!(f, "show the usage of today"),
+            Kind::OutputPolicy => write!(f, "how mentions and invite links in results are treated (`strict`, `mentions`, `off`)"),
+            Kind::OutputLimit => write!(f, "lines and bytes of results shown inline"),
+            Kind::RenderStyle => write!(f, "how results are shown (`embed`, `text`)"),
+            Kind::Prefix => write!(f, "change the command prefix"),
+            Kind::Locale => write!(f, "set the language of messages (`ja`, `en`)"),
+            Kind::HideWarnings => write!(f, "hide compiler warnings if the program runs"),
+            Kind::ShowWarnings => write!(f, "always show compiler warnings"),
+            Kind::Export => write!(f, "export the settings of all the channels as a file"),
+            Kind::Import => write!(f, "import exported settings from a file or a code block (`--dry-run` to only show the changes)"),
+            Kind::ListLanguages => write!(f, "list languages"),
+            Kind::List => write!(f, "list compilers for a language"),
+            Kind::Run => write!(f, "run"),
+            Kind::RunSave => write!(f, "run and save"),
+        },
+        Text::Aliases(a) => write!(f, "Aliases: {}", a),
+        Text::RequiredPermission(p) => write!(f, "Required permission: `{}`", p),
+        Text::SlashLanguage => write!(f, "language"),
+        Text::SlashCompiler => write!(f, "compiler"),
+        Text::SlashOptions => write!(f, "options passed to the compiler"),
+        Text::SlashScope => write!(f, "the scope of the setting (this channel if omitted)"),
+        Text::CodeModalTitle => write!(f, "Paste your code"),
+        Text::CodeModalCode => write!(f, "Code"),
+        Text::CodeModalStdin => write!(f, "Standard input"),
+        Text::NoAuditEntries => write!(f, "No changes recorded"),
+        Text::NoChanges => write!(f, "no changes"),
+        Text::DryRun => write!(f, "(dry run)"),
+        Text::InferredLanguage(l) => write!(f, "Ran as {}", l),
+        Text::OutputAttached => write!(f, "The output is long, so the whole is attached"),
+        Text::ExitedWithStatus(s) => write!(f, "exited with status code {}", s),
+        Text::CompilationFailed(s) => write!(f, "compilation failed with status code {}", s),
+        Text::ExitedWithSignal(s) => write!(f, "exited with signal: {}", s),
+        Text::TimedOut => write!(f, "timed out"),
+        Text::ExitedWithUnknownStatus(s) => write!(f, "exited with unknown status: {}", s),
+        Text::ExitedWithoutStatus => write!(f, "exited without status"),
+        Text::UnknownLanguageName(name) => write!(f, "`{}`? Hmm...", name),
+        Text::UnknownCompilerName(name) => write!(f, "What is `{}`? lol", name),
+        Text::UnknownCompilerSpec(name) => write!(
+            f,
+            "What do you mean by `{}`? Nobody would normally write `{}`...",
+            name, name
+        ),
+        Text::UnmappedLanguage(name) => write!(f, "No compiler is chosen for `{}`", name),
+        Text::NoCompilerSpecified => write!(f, "I can't tell which compiler to use~"),
+        Text::RemapMismatch { compiler, language } => {
+            write!(f, "Well, `{}` can't compile `{}`", compiler, language)
+        }
+        Text::UnsupportedDocumentVersion(v) => {
+            write!(f, "I can't read setting files of version {}", v)
+        }
+        Text::InvalidDocumentValue { key, value } => {
+            write!(f, "`{}` can't be set to `{}`", key, value)
+        }
+        Text::AccessDenied(t) => write!(f, "Looks like I'm not allowed to `{}` here", t),
+        Text::RateLimited { secs } => write!(f, "Calm down! Try again in {} seconds", secs),
+        Text::QuotaExceeded(k) => write!(f, "The limit of `{}` for today is reached, see you tomorrow", k),
+        Text::TooManyInFlight => write!(f, "I'm busy right now, try again in a moment"),
+        Text::InvalidChannelID(id) => write!(f, "`{}` is not a channel ID", id),
+        Text::InvalidSettingFile(e) => write!(f, "The setting file is broken: {}", e),
+        Text::InvalidCodeInput => write!(f, "The code input is malformed"),
+        Text::MalformedArguments => write!(f, "Hey, no mischief please"),
+        Text::InvalidArguments { usages } => write!(f, "Wrong arguments, the usage is\n{}", usages),
+        Text::UnknownCommand(c) => write!(f, "`{}`, totally understood", c),
+        Text::InvalidValue { value, expected } => {
+            write!(f, "`{}` won't do, use one of {}", value, expected)
+        }
+        Text::GuildOnly(c) => write!(f, "`{}` is only available in servers", c),
+        Text::CommandIsMissing => write!(f, "?"),
+        Text::PermissionDenied(p) => write!(f, "You need the `{}` permission to do that", p),
+        Text::UnknownPermission(p) => write!(f, "I don't know the permission `{}`", p),
+        Text::UnknownOutputPolicy(p) => write!(f, "I don't know the setting `{}`", p),
+        Text::UnknownRenderStyle(s) => write!(f, "I don't know the style `{}`", s),
+        Text::UnknownLocale(l) => write!(f, "I don't speak `{}`", l),
+        Text::InvalidNumber(n) => write!(f, "`{}` is not a number", n),
+        Text::InvalidMention(m) => write!(f, "`{}` should be a mention of a role or a user", m),
+        Text::TooManyAttachments(n) => write!(f, "Up to {} attachments, please", n),
+        Text::TooLargeAttachment { name, max_kib } => {
+            write!(f, "`{}` is too large (up to {}KiB)", name, max_kib)
+        }
+        Text::Encoding => write!(f, "Please write attachments in UTF-8"),
+        Text::InvalidPrefix { prefix, max_length } => write!(
+            f,
+            "`{}` can't be a prefix (use up to {} characters without spaces)",
+            prefix, max_length
+        ),
+        Text::TooLongCommandline(n) => write!(f, "Keep commands within {} characters", n),
+        Text::UnknownError => write!(f, "Sorry"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::command::COMMANDS;
+
+    fn texts<'a>(arg: Arg<'a>) -> Vec<Text<'a>> {
+        let mut texts = vec![
+            Text::HelpHeader {
+                prefix: arg,
+                run_reaction: arg,
+            },
+            Text::Aliases(arg),
+            Text::RequiredPermission(arg),
+            Text::SlashLanguage,
+            Text::SlashCompiler,
+            Text::SlashOptions,
+            Text::SlashScope,
+            Text::CodeModalTitle,
+            Text::CodeModalCode,
+            Text::CodeModalStdin,
+            Text::NoAuditEntries,
+            Text::NoChanges,
+            Text::DryRun,
+            Text::InferredLanguage(arg),
+            Text::OutputAttached,
+            Text::ExitedWithStatus(arg),
+            Text::CompilationFailed(arg),
+            Text::ExitedWithSignal(arg),
+            Text::TimedOut,
+            Text::ExitedWithUnknownStatus(arg),
+            Text::ExitedWithoutStatus,
+            Text::UnknownLanguageName(arg),
+            Text::UnknownCompilerName(arg),
+            Text::UnknownCompilerSpec(arg),
+            Text::UnmappedLanguage(arg),
+            Text::NoCompilerSpecified,
+            Text::RemapMismatch {
+                compiler: arg,
+                language: arg,
+            },
+            Text::UnsupportedDocumentVersion(arg),
+            Text::InvalidDocumentValue {
+                key: arg,
+                value: arg,
+            },
+            Text::AccessDenied(arg),
+            Text::RateLimited { secs: arg },
+            Text::QuotaExceeded(arg),
+            Text::TooManyInFlight,
+            Text::InvalidChannelID(arg),
+            Text::InvalidSettingFile(arg),
+            Text::InvalidCodeInput,
+            Text::MalformedArguments,
+            Text::InvalidArguments { usages: arg },
+            Text::UnknownCommand(arg),
+            Text::InvalidValue {
+                value: arg,
+                expected: arg,
+            },
+            Text::GuildOnly(arg),
+            Text::CommandIsMissing,
+            Text::PermissionDenied(arg),
+            Text::UnknownPermission(arg),
+            Text::UnknownOutputPolicy(arg),
+            Text::UnknownRenderStyle(arg),
+            Text::UnknownLocale(arg),
+            Text::InvalidNumber(arg),
+            Text::InvalidMention(arg),
+            Text::TooManyAttachments(arg),
+            Text::TooLargeAttachment {
+                name: arg,
+                max_kib: arg,
+            },
+            Text::Encoding,
+            Text::InvalidPrefix {
+                prefix: arg,
+                max_length: arg,
+            },
+            Text::TooLongCommandline(arg),
+            Text::UnknownError,
+        ];
+        texts.extend(COMMANDS.iter().map(|c| Text::Description(c.kind)));
+        texts
+    }
+
+    #[test]
+    fn every_text_is_translated() {
+        for &locale in &[Locale::Ja, Locale::En] {
+            for text in texts(&"x") {
+                assert!(!localize(text, locale).trim().is_empty());
+            }
+        }
+    }
+}
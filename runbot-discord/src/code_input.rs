@@ -1,6 +1,8 @@
 use std::str::{pattern::Pattern, FromStr};
 
-use runbot::model::code::Code;
+use runbot::model::code::{Code, CodeFile};
+
+use super::attachment::AttachedFile;
 
 use err_derive::Error;
 
@@ -75,6 +77,8 @@ impl CodeBlock {
 pub struct CodeInput {
     code: CodeBlock,
     stdin: Option<String>,
+    // attached source files other than the main code
+    files: Vec<AttachedFile>,
 }
 
 #[derive(Debug, Error)]
@@ -90,7 +94,19 @@ pub enum ParseCodeInputError {
 impl FromStr for CodeInput {
     type Err = ParseCodeInputError;
     fn from_str(s: &str) -> Result<CodeInput, ParseCodeInputError> {
-        let mut blocks = itertools::unfold(s, |input| {
+        CodeInput::with_attachments(s, Vec::new())
+    }
+}
+
+impl CodeInput {
+    // Code blocks in `s` come first, then the attached files.
+    // Without a code block, the first attached source file is the main code and the rest are
+    // compiled along with it. `stdin.txt` is used as the stdin.
+    pub fn with_attachments(
+        s: &str,
+        files: Vec<AttachedFile>,
+    ) -> Result<CodeInput, ParseCodeInputError> {
+        let blocks = itertools::unfold(s, |input| {
             if let Some((block, rest)) = parse_code_block(input) {
                 *input = rest;
                 Some(block)
@@ -99,26 +115,54 @@ impl FromStr for CodeInput {
             }
         });
 
-        match (blocks.next(), blocks.next(), blocks.next()) {
-            (None, _, _) => Err(ParseCodeInputError::NoCodeBlockFound),
-            (Some(code), None, _) if code.is_stdin() => Err(ParseCodeInputError::OnlyStdinFound),
-            (Some(code), None, _) => Ok(CodeInput { code, stdin: None }),
-            (Some(code1), Some(code2), None) if code1.is_stdin() => Ok(CodeInput {
-                code: code2,
-                stdin: Some(code1.code),
-            }),
-            (Some(code1), Some(code2), None) if code2.is_stdin() => Ok(CodeInput {
-                code: code1,
-                stdin: Some(code2.code),
-            }),
-            (Some(_), Some(_), _) => Err(ParseCodeInputError::TooManyCodeBlocks),
+        let (stdin_blocks, mut code_blocks): (Vec<_>, Vec<_>) =
+            blocks.partition(CodeBlock::is_stdin);
+        let (stdin_files, mut files): (Vec<_>, Vec<_>) =
+            files.into_iter().partition(AttachedFile::is_stdin);
+
+        let mut stdin = stdin_blocks
+            .into_iter()
+            .map(|b| b.code)
+            .chain(stdin_files.into_iter().map(|f| f.text));
+        let (stdin, rest) = (stdin.next(), stdin.next());
+        if code_blocks.len() > 1 || rest.is_some() {
+            return Err(ParseCodeInputError::TooManyCodeBlocks);
+        }
+
+        let code = match code_blocks.pop() {
+            Some(code) => code,
+            None if !files.is_empty() => {
+                let file = files.remove(0);
+                CodeBlock {
+                    language: file.extension().map(str::to_owned),
+                    code: file.text,
+                }
+            }
+            None if stdin.is_some() => return Err(ParseCodeInputError::OnlyStdinFound),
+            None => return Err(ParseCodeInputError::NoCodeBlockFound),
+        };
+
+        Ok(CodeInput { code, stdin, files })
+    }
+
+    // code given as is, such as in a modal, rather than in a markdown message
+    pub fn new(code: String, stdin: Option<String>) -> CodeInput {
+        CodeInput {
+            code: CodeBlock {
+                language: None,
+                code,
+            },
+            stdin,
+            files: Vec::new(),
         }
     }
-}
 
-impl CodeInput {
     pub fn into_code(self) -> Code {
-        self.code.into_code()
+        let mut code = self.code.into_code();
+        for file in self.files {
+            code.add_file(CodeFile::new(file.filename, file.text));
+        }
+        code
     }
 
     pub fn stdin(&self) -> Option<&String> {
@@ -0,0 +1,334 @@
+use std::fmt;
+
+use runbot::model::scope::Scope;
+
+use super::error::{Error, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Help,
+    ShowSetting,
+    Auto,
+    NoAuto,
+    AutoSave,
+    NoAutoSave,
+    Remap,
+    Options,
+    Reset,
+    Audit,
+    LogChannel,
+    NoLogChannel,
+    Permission,
+    Access,
+    Quota,
+    Usage,
+    OutputPolicy,
+    OutputLimit,
+    RenderStyle,
+    Prefix,
+    Locale,
+    HideWarnings,
+    ShowWarnings,
+    Export,
+    Import,
+    ListLanguages,
+    List,
+    Run,
+    RunSave,
+}
+
+// the permission a command requires unless configured otherwise
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Requirement {
+    None,
+    // changes settings of a channel (or the whole guild with `global`, or oneself with `my`)
+    Channel,
+    // always concerns the whole guild
+    Guild,
+    // concerns the whole guild, or oneself with `my`
+    Personal,
+    // concerns the whole guild, and cannot be configured
+    Fixed,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Argument {
+    pub name: &'static str,
+    // takes all the rest of the arguments, possibly none
+    pub variadic: bool,
+}
+
+const fn arg(name: &'static str) -> Argument {
+    Argument {
+        name,
+        variadic: false,
+    }
+}
+
+const fn rest(name: &'static str) -> Argument {
+    Argument {
+        name,
+        variadic: true,
+    }
+}
+
+#[derive(Debug)]
+pub struct Command {
+    pub kind: Kind,
+    pub name: &'static str,
+    pub aliases: &'static [&'static str],
+    // the accepted forms of the arguments
+    pub usages: &'static [&'static [Argument]],
+    pub requirement: Requirement,
+    pub scopes: &'static [Scope],
+    // available in direct messages, where every setting is personal
+    pub direct: bool,
+}
+
+const NO_ARGUMENTS: &[&[Argument]] = &[&[]];
+
+const ALL_SCOPES: &[Scope] = &[Scope::Channel, Scope::Guild, Scope::User];
+
+const RUN_USAGES: &[&[Argument]] = &[&[], &[arg("compiler"), rest("options")]];
+
+const fn command(kind: Kind, name: &'static str) -> Command {
+    Command {
+        kind,
+        name,
+        aliases: &[],
+        usages: NO_ARGUMENTS,
+        requirement: Requirement::None,
+        scopes: ALL_SCOPES,
+        direct: true,
+    }
+}
+
+// in the order shown in `help`
+pub const COMMANDS: &[Command] = &[
+    Command {
+        aliases: &["h"],
+        usages: &[&[], &[arg("command")]],
+        ..command(Kind::Help, "help")
+    },
+    command(Kind::ShowSetting, "show-setting"),
+    Command {
+        requirement: Requirement::Channel,
+        ..command(Kind::Auto, "auto")
+    },
+    Command {
+        requirement: Requirement::Channel,
+        ..command(Kind::NoAuto, "no-auto")
+    },
+    Command {
+        requirement: Requirement::Channel,
+        ..command(Kind::AutoSave, "auto-save")
+    },
+    Command {
+        requirement: Requirement::Channel,
+        ..command(Kind::NoAutoSave, "no-auto-save")
+    },
+    Command {
+        usages: &[&[arg("language"), arg("compiler")]],
+        requirement: Requirement::Channel,
+        ..command(Kind::Remap, "remap")
+    },
+    Command {
+        usages: &[&[arg("compiler"), rest("options")]],
+        requirement: Requirement::Channel,
+        ..command(Kind::Options, "options")
+    },
+    Command {
+        scopes: &[Scope::User],
+        ..command(Kind::Reset, "reset")
+    },
+    Command {
+        usages: &[&[], &[arg("count")]],
+        scopes: &[Scope::Channel, Scope::Guild],
+        direct: false,
+        ..command(Kind::Audit, "audit")
+    },
+    Command {
+        usages: &[&[arg("channel")]],
+        requirement: Requirement::Guild,
+        direct: false,
+        ..command(Kind::LogChannel, "log-channel")
+    },
+    Command {
+        requirement: Requirement::Guild,
+        direct: false,
+        ..command(Kind::NoLogChannel, "no-log-channel")
+    },
+    Command {
+        usages: &[&[], &[arg("command")], &[arg("command"), arg("permission")]],
+        requirement: Requirement::Fixed,
+        direct: false,
+        ..command(Kind::Permission, "permission")
+    },
+    Command {
+        usages: &[
+            &[],
+            &[arg("allow|deny|clear"), arg("target"), arg("mention")],
+        ],
+        requirement: Requirement::Guild,
+        direct: false,
+        ..command(Kind::Access, "access")
+    },
+    Command {
+        usages: &[&[arg("key"), arg("limit")]],
+        requirement: Requirement::Guild,
+        direct: false,
+        scopes: &[Scope::Channel, Scope::Guild],
+        ..command(Kind::Quota, "quota")
+    },
+    Command {
+        direct: false,
+        ..command(Kind::Usage, "usage")
+    },
+    Command {
+        usages: &[&[], &[arg("policy")]],
+        requirement: Requirement::Guild,
+        direct: false,
+        ..command(Kind::OutputPolicy, "output-policy")
+    },
+    Command {
+        usages: &[&[], &[arg("lines|bytes"), arg("limit")]],
+        requirement: Requirement::Guild,
+        direct: false,
+        ..command(Kind::OutputLimit, "output-limit")
+    },
+    Command {
+        usages: &[&[], &[arg("style")]],
+        requirement: Requirement::Guild,
+        direct: false,
+        ..command(Kind::RenderStyle, "render-style")
+    },
+    Command {
+        usages: &[&[], &[arg("prefix")]],
+        requirement: Requirement::Guild,
+        direct: false,
+        ..command(Kind::Prefix, "prefix")
+    },
+    Command {
+        usages: &[&[], &[arg("locale")]],
+        requirement: Requirement::Personal,
+        ..command(Kind::Locale, "locale")
+    },
+    Command {
+        requirement: Requirement::Guild,
+        direct: false,
+        ..command(Kind::HideWarnings, "hide-warnings")
+    },
+    Command {
+        requirement: Requirement::Guild,
+        direct: false,
+        ..command(Kind::ShowWarnings, "show-warnings")
+    },
+    Command {
+        usages: &[&[], &[arg("toml|json")]],
+        requirement: Requirement::Guild,
+        direct: false,
+        ..command(Kind::Export, "export")
+    },
+    Command {
+        usages: &[&[], &[arg("--dry-run")]],
+        requirement: Requirement::Guild,
+        direct: false,
+        ..command(Kind::Import, "import")
+    },
+    Command {
+        aliases: &["langs"],
+        ..command(Kind::ListLanguages, "list-languages")
+    },
+    Command {
+        aliases: &["ls"],
+        usages: &[&[arg("language")]],
+        ..command(Kind::List, "list")
+    },
+    Command {
+        aliases: &["r"],
+        usages: RUN_USAGES,
+        ..command(Kind::Run, "run")
+    },
+    Command {
+        aliases: &["rs"],
+        usages: RUN_USAGES,
+        ..command(Kind::RunSave, "run-save")
+    },
+];
+
+// looks up a command by its name or an alias
+pub fn find(name: &str) -> Option<&'static Command> {
+    COMMANDS
+        .iter()
+        .find(|c| c.name == name || c.aliases.contains(&name))
+}
+
+// Finds the command in `words`, and checks the arguments that follow.
+// Commands not available in `scope` are treated as unknown.
+pub fn parse(
+    words: &[String],
+    scope: Scope,
+    direct: bool,
+) -> Result<(&'static Command, &[String])> {
+    let (name, arguments) = match words.split_first() {
+        Some(x) => x,
+        None => return Err(Error::CommandIsMissing),
+    };
+    let command = find(name).ok_or_else(|| Error::UnknownCommand(name.to_string()))?;
+    // checked first, as the scope is always personal in direct messages
+    if direct && !command.direct {
+        return Err(Error::GuildOnly(command.name.to_string()));
+    }
+    if !command.scopes.contains(&scope) {
+        return Err(Error::UnknownCommand(name.to_string()));
+    }
+    if !command.accepts(arguments) {
+        return Err(Error::InvalidArguments(command.name.to_string()));
+    }
+    Ok((command, arguments))
+}
+
+impl Command {
+    pub fn accepts(&self, arguments: &[impl AsRef<str>]) -> bool {
+        self.usages.iter().any(|usage| match usage.last() {
+            Some(last) if last.variadic => arguments.len() + 1 >= usage.len(),
+            _ => arguments.len() == usage.len(),
+        })
+    }
+
+    // the words before the arguments, like `my reset`
+    pub fn words(&self) -> String {
+        if self.scopes == [Scope::User] {
+            format!("my {}", self.name)
+        } else {
+            self.name.to_string()
+        }
+    }
+
+    pub fn usages(&self) -> impl Iterator<Item = Usage> {
+        self.usages.iter().map(move |arguments| Usage {
+            command: self,
+            arguments,
+        })
+    }
+}
+
+// a form of the command line, like `remap <language> <compiler>`
+pub struct Usage<'a> {
+    command: &'a Command,
+    arguments: &'a [Argument],
+}
+
+impl fmt::Display for Usage<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.command.words())?;
+        for argument in self.arguments {
+            if argument.variadic {
+                write!(f, " [{}...]", argument.name)?;
+            } else {
+                write!(f, " <{}>", argument.name)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -1,17 +1,51 @@
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::sync::Arc;
 use std::{fmt, ops, str};
 
 use super::display::Display;
 use super::error::Result;
+use super::interaction::{self, Interaction};
+use super::locale::{self, Locale, DEFAULT_LOCALE};
+use super::reply::Replies;
+
+use runbot::model::role_id::RoleID;
+use runbot::model::scope::Scope;
 
 use itertools::Itertools;
+use serde_json::Value;
+use serenity::builder::CreateEmbed;
 use serenity::client::Context;
+use serenity::http::AttachmentType;
 use serenity::model::channel::{Message, ReactionType};
+use serenity::model::guild::Guild;
+use serenity::model::id::{ChannelId, GuildId, MessageId, RoleId, UserId};
+use serenity::prelude::RwLock;
+
+pub enum Source {
+    Message(Box<Message>),
+    // replies go through the interaction token, instead of the bot itself
+    Interaction {
+        interaction: Box<Interaction>,
+        client: interaction::Client,
+        // whether the deferred response has been replaced
+        responded: Cell<bool>,
+    },
+}
 
 pub struct CommandContext {
     pub ctx: Context,
-    pub message: Message,
-    pub is_global: bool,
+    pub source: Source,
+    pub channel_id: ChannelId,
+    pub guild_id: Option<GuildId>,
+    pub scope: Scope,
+    // the user who asked for the handling; differs from the author if triggered by a reaction
+    pub invoker: UserId,
     pub runbot_ctx: runbot::Context,
+    // messages sent in reply while handling `message`
+    replies: RefCell<Vec<MessageId>>,
+    // replies to the previous version of `message`, reused by editing them
+    stale_replies: RefCell<VecDeque<MessageId>>,
 }
 
 impl ops::Deref for CommandContext {
@@ -21,40 +55,281 @@ impl ops::Deref for CommandContext {
     }
 }
 
+fn no_mentions() -> Value {
+    serde_json::json!({ "parse": [] })
+}
+
 impl CommandContext {
     pub fn new(ctx: Context, message: Message, runbot_ctx: runbot::Context) -> CommandContext {
         CommandContext {
             ctx,
-            message,
-            is_global: false,
+            channel_id: message.channel_id,
+            guild_id: message.guild_id,
+            scope: Scope::Channel,
+            invoker: message.author.id,
+            source: Source::Message(Box::new(message)),
+            runbot_ctx,
+            replies: RefCell::new(Vec::new()),
+            stale_replies: RefCell::new(VecDeque::new()),
+        }
+    }
+
+    // `interaction` must have been deferred
+    pub fn from_interaction(
+        ctx: Context,
+        interaction: Interaction,
+        invoker: UserId,
+        client: interaction::Client,
+        runbot_ctx: runbot::Context,
+    ) -> CommandContext {
+        CommandContext {
+            ctx,
+            channel_id: interaction.channel_id,
+            guild_id: interaction.guild_id,
+            scope: Scope::Channel,
+            invoker,
+            source: Source::Interaction {
+                interaction: Box::new(interaction),
+                client,
+                responded: Cell::new(false),
+            },
             runbot_ctx,
+            replies: RefCell::new(Vec::new()),
+            stale_replies: RefCell::new(VecDeque::new()),
+        }
+    }
+
+    // the message being handled, if not an interaction
+    pub fn message(&self) -> Option<&Message> {
+        match &self.source {
+            Source::Message(message) => Some(message.as_ref()),
+            Source::Interaction { .. } => None,
         }
     }
 
+    pub fn interaction(&self) -> Option<&Interaction> {
+        match &self.source {
+            Source::Message(_) => None,
+            Source::Interaction { interaction, .. } => Some(interaction.as_ref()),
+        }
+    }
+
+    pub fn guild(&self) -> Option<Arc<RwLock<Guild>>> {
+        self.guild_id?.to_guild_cached(&self.ctx.cache)
+    }
+
+    pub fn reuse_replies(&mut self, message_ids: Vec<MessageId>) {
+        self.stale_replies = RefCell::new(message_ids.into());
+    }
+
+    pub fn replies(&self) -> Replies {
+        // the replies belong to the author of the code, not to whoever reacted to it
+        let author_id = match &self.source {
+            Source::Message(message) => message.author.id,
+            Source::Interaction { .. } => self.invoker,
+        };
+        Replies {
+            channel_id: self.channel_id,
+            author_id,
+            message_ids: self.replies.borrow().clone(),
+        }
+    }
+
+    // deletes the stale replies which were not reused
+    pub fn delete_stale_replies(&self) -> Result<()> {
+        for id in self.stale_replies.borrow_mut().drain(..) {
+            self.channel_id.delete_message(&self.ctx.http, id)?;
+        }
+        Ok(())
+    }
+
+    // Sends a reply, or edits a stale one instead if any.
+    // Nobody is pinged by mentions in the reply, as it may contain program output.
+    fn reply(&self, content: String, embed: Option<Value>) -> Result<()> {
+        if let Source::Interaction {
+            interaction,
+            client,
+            responded,
+        } = &self.source
+        {
+            let mut payload = serde_json::json!({
+                "content": content,
+                "allowed_mentions": no_mentions(),
+            });
+            if let Some(embed) = embed {
+                payload["embeds"] = Value::Array(vec![embed]);
+            }
+            let id = if responded.replace(true) {
+                client.follow_up(interaction, &payload, Vec::new())?
+            } else {
+                client.edit_response(interaction, &payload)?
+            };
+            self.replies.borrow_mut().push(id);
+            return Ok(());
+        }
+
+        let channel_id = self.channel_id;
+        let stale_reply = self.stale_replies.borrow_mut().pop_front();
+        let message = match stale_reply {
+            Some(id) => channel_id.edit_message(&self.ctx.http, id, |m| {
+                m.0.insert("content", Value::String(content));
+                m.0.insert("embed", embed.unwrap_or(Value::Null));
+                m.0.insert("allowed_mentions", no_mentions());
+                m
+            })?,
+            None => channel_id.send_message(&self.ctx.http, |m| {
+                m.content(content);
+                if let Some(embed) = embed {
+                    m.0.insert("embed", embed);
+                }
+                m.0.insert("allowed_mentions", no_mentions());
+                m
+            })?,
+        };
+        self.replies.borrow_mut().push(message.id);
+        Ok(())
+    }
+
     pub fn say(&self, message: impl AsRef<str>) -> Result<()> {
+        for msg in message.as_ref().chars().chunks(2000).into_iter() {
+            self.reply(msg.collect(), None)?;
+        }
+        Ok(())
+    }
+
+    // send to another channel, without pinging anyone mentioned in the message
+    pub fn say_to(&self, channel_id: ChannelId, message: impl AsRef<str>) -> Result<()> {
         for msg in message.as_ref().chars().chunks(2000).into_iter() {
             let msg_str: String = msg.collect();
-            self.message.channel_id.say(&self.ctx.http, &msg_str)?;
+            channel_id.send_message(&self.ctx.http, |m| {
+                m.content(msg_str);
+                m.0.insert("allowed_mentions", no_mentions());
+                m
+            })?;
         }
         Ok(())
     }
 
-    pub fn react(&self, reaction: ReactionType) -> Result<()> {
-        self.message.react(&self.ctx.http, reaction)?;
+    // Attachments cannot be added by editing, so this always sends a new message.
+    // Attachments are sent in multipart, where everything else goes into `payload_json`.
+    fn reply_with_files(&self, mut payload: Value, files: Vec<AttachmentType>) -> Result<()> {
+        payload["allowed_mentions"] = no_mentions();
+        if let Source::Interaction {
+            interaction,
+            client,
+            responded,
+        } = &self.source
+        {
+            // embeds of webhooks are given in an array
+            if let Some(embed) = payload.get_mut("embed").map(Value::take) {
+                payload["embeds"] = Value::Array(vec![embed]);
+            }
+            let id = client.follow_up(interaction, &payload, files)?;
+            responded.set(true);
+            self.replies.borrow_mut().push(id);
+            return Ok(());
+        }
+
+        let message = self.channel_id.send_files(&self.ctx.http, files, |m| {
+            m.0.insert("payload_json", payload);
+            m
+        })?;
+        self.replies.borrow_mut().push(message.id);
         Ok(())
     }
 
+    pub fn send_files(&self, message: impl AsRef<str>, files: Vec<AttachmentType>) -> Result<()> {
+        let payload = serde_json::json!({ "content": message.as_ref() });
+        self.reply_with_files(payload, files)
+    }
+
+    pub fn send_embed(&self, embed: CreateEmbed, files: Vec<AttachmentType>) -> Result<()> {
+        let embed = Value::Object(serenity::utils::hashmap_to_json_map(embed.0));
+        if files.is_empty() {
+            self.reply(String::new(), Some(embed))
+        } else {
+            self.reply_with_files(serde_json::json!({ "embed": embed }), files)
+        }
+    }
+
+    pub fn role_ids(&self) -> Vec<RoleID> {
+        let to_role_ids = |roles: &[RoleId]| {
+            roles
+                .iter()
+                .map(|id| RoleID::from_u64(*id.as_u64()))
+                .collect()
+        };
+
+        match &self.source {
+            Source::Message(message) if message.author.id == self.invoker => {
+                if let Some(member) = &message.member {
+                    return to_role_ids(&member.roles);
+                }
+            }
+            Source::Interaction { interaction, .. } => {
+                if let Some(member) = &interaction.member {
+                    return to_role_ids(&member.roles);
+                }
+            }
+            _ => {}
+        }
+
+        // messages fetched over HTTP do not carry the member
+        match self.guild() {
+            Some(guild) => match guild.read().members.get(&self.invoker) {
+                Some(member) => to_role_ids(&member.roles),
+                None => Vec::new(),
+            },
+            None => Vec::new(),
+        }
+    }
+
+    // the message replied to, if any (crossposts also carry a reference, to another channel)
+    pub fn referenced_message(&self) -> Result<Option<Message>> {
+        let message = match self.message() {
+            Some(message) => message,
+            None => return Ok(None),
+        };
+        let reference = match &message.message_reference {
+            Some(reference) if reference.channel_id == self.channel_id => reference,
+            _ => return Ok(None),
+        };
+        let message_id = match reference.message_id {
+            Some(id) => id,
+            None => return Ok(None),
+        };
+
+        let message = reference.channel_id.message(&self.ctx.http, message_id)?;
+        Ok(Some(message))
+    }
+
+    // Interactions cannot be reacted to, so the reaction is sent as a reply instead.
+    pub fn react(&self, reaction: ReactionType) -> Result<()> {
+        match &self.source {
+            Source::Message(message) => {
+                message.react(&self.ctx.http, reaction)?;
+                Ok(())
+            }
+            Source::Interaction { .. } => self.say(reaction.to_string()),
+        }
+    }
+
+    // falls back to the default, so that errors can be reported whatever happens
+    pub fn locale(&self) -> Locale {
+        locale::locale(self).unwrap_or(DEFAULT_LOCALE)
+    }
+
     pub fn display<'a, T>(&self, x: &'a T) -> Result<()>
     where
         Display<'a, T>: fmt::Display,
     {
-        self.say(Display(x).to_string())
+        self.say(Display(x, self.locale()).to_string())
     }
 
     pub fn display_in_code_block<'a, T>(&self, x: &'a T) -> Result<()>
     where
         Display<'a, T>: fmt::Display,
     {
-        self.say(format!("```{}```", Display(x)))
+        self.say(format!("```{}```", Display(x, self.locale())))
     }
 }
@@ -1,16 +1,26 @@
 use std::fmt;
 
 use runbot::action;
+use runbot::model::access::{AccessEntry, Principal};
+use runbot::model::audit::{AuditEntry, AuditScope};
 use runbot::model::compiler::Compiler;
 use runbot::model::language::Language;
+use runbot::model::outcome::{Outcome, Status};
+use runbot::model::streams::Stream;
 
-use super::compile_result::CompileResult;
+use super::attachment;
+use super::catalog::{Localized, Text};
+use super::command::{self, Command};
 use super::error::Error;
+use super::locale::Locale;
+use super::permission;
+use super::prefix;
 
 use itertools::Itertools;
 use tabular::Row;
 
-pub struct Display<'a, T>(pub &'a T);
+// the locale is unused for tables and the like, which contain no messages
+pub struct Display<'a, T>(pub &'a T, pub Locale);
 
 impl fmt::Display for Display<'_, Vec<Compiler>> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -29,12 +39,13 @@ impl fmt::Display for Display<'_, Vec<Compiler>> {
 
 impl fmt::Display for Display<'_, Vec<Language>> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let mut table = tabular::Table::new("{:<}  {:<}");
+        let mut table = tabular::Table::new("{:<}  {:<}  {:<}");
         for l in self.0 {
             table.add_row(
                 Row::new()
                     .with_cell(l.name())
-                    .with_cell(l.aliases().iter().join(",")),
+                    .with_cell(l.aliases().iter().join(","))
+                    .with_cell(l.extensions().iter().map(|e| format!(".{}", e)).join(",")),
             );
         }
         write!(f, "{}", table)
@@ -54,72 +65,345 @@ impl fmt::Display for Display<'_, action::dump_setting::Output> {
         for (l, c) in &self.0.remap {
             table.add_row(Row::new().with_cell(l).with_cell(c));
         }
+        table.add_heading("options:");
+        for (c, o) in &self.0.options {
+            table.add_row(Row::new().with_cell(c).with_cell(o.into_iter().join(" ")));
+        }
+        table.add_heading("access:");
+        for entry in &self.0.access {
+            table.add_row(Row::new().with_cell(entry.target).with_cell(format!(
+                "{} {}",
+                entry.rule.name(),
+                Display(&entry.principal, self.1)
+            )));
+        }
         write!(f, "{}", table)
     }
 }
 
-impl fmt::Display for Display<'_, Error> {
+impl fmt::Display for Display<'_, Vec<AccessEntry>> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut table = tabular::Table::new("{:<}  {:<}  {:<}");
+        for entry in self.0 {
+            table.add_row(
+                Row::new()
+                    .with_cell(entry.target)
+                    .with_cell(entry.rule.name())
+                    .with_cell(Display(&entry.principal, self.1)),
+            );
+        }
+        write!(f, "{}", table)
+    }
+}
+
+impl fmt::Display for Display<'_, action::usage::Output> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut table = tabular::Table::new("{:<}  {:>} / {:<}");
+        for (key, limit) in &self.0.quotas {
+            let limit = limit.map_or_else(|| "-".to_string(), |l| l.to_string());
+            table.add_row(
+                Row::new()
+                    .with_cell(key)
+                    .with_cell(self.0.usage_of(*key))
+                    .with_cell(limit),
+            );
+        }
+        write!(f, "{}", table)
+    }
+}
+
+impl fmt::Display for Display<'_, Principal> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self.0 {
+            Principal::Role(id) => write!(f, "role:{}", id.as_u64()),
+            Principal::User(id) => write!(f, "user:{}", id.as_u64()),
+        }
+    }
+}
+
+impl fmt::Display for Display<'_, action::dump_user_setting::Output> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fn or_unset(x: Option<bool>) -> String {
+            x.map_or_else(|| "-".to_string(), |x| x.to_string())
+        }
+
+        let mut table = tabular::Table::new("{:<}  {:<}");
+        table.add_row(
+            Row::new()
+                .with_cell("auto")
+                .with_cell(or_unset(self.0.auto)),
+        );
+        table.add_row(
+            Row::new()
+                .with_cell("auto-save")
+                .with_cell(or_unset(self.0.auto_save)),
+        );
+        table.add_row(
+            Row::new()
+                .with_cell("locale")
+                .with_cell(self.0.locale.as_deref().unwrap_or("-")),
+        );
+        table.add_heading("remap:");
+        for (l, c) in &self.0.remap {
+            table.add_row(Row::new().with_cell(l).with_cell(c));
+        }
+        table.add_heading("options:");
+        for (c, o) in &self.0.options {
+            table.add_row(Row::new().with_cell(c).with_cell(o.into_iter().join(" ")));
+        }
+        write!(f, "{}", table)
+    }
+}
+
+impl fmt::Display for Display<'_, action::import_setting::Output> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut table = tabular::Table::new("{:<}  {:<}  {:<}  {:<}");
+        for change in &self.0.changes {
+            let scope = match change.channel_id {
+                Some(id) => id.as_u64().to_string(),
+                None => "default".to_string(),
+            };
+            let old = change.old.as_deref().unwrap_or("-");
+            table.add_row(
+                Row::new()
+                    .with_cell(scope)
+                    .with_cell(&change.key)
+                    .with_cell(old)
+                    .with_cell(format!("-> {}", change.new)),
+            );
+        }
+        write!(f, "{}", table)?;
+
+        if self.0.changes.is_empty() {
+            writeln!(f, "{}", Localized(Text::NoChanges, self.1))?;
+        }
+        if !self.0.applied {
+            writeln!(f, "{}", Localized(Text::DryRun, self.1))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for Display<'_, Vec<AuditEntry>> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.0.is_empty() {
+            return writeln!(f, "{}", Localized(Text::NoAuditEntries, self.1));
+        }
+
+        for e in self.0 {
+            let scope = match e.scope {
+                AuditScope::Channel(id) => format!("<#{}>", id.as_u64()),
+                AuditScope::Guild => "global".to_string(),
+            };
+            writeln!(
+                f,
+                "<t:{}:f> <@{}> {} `{}`: `{}` → `{}`",
+                e.timestamp,
+                e.actor.as_u64(),
+                scope,
+                e.key,
+                e.old.as_deref().unwrap_or("-"),
+                e.new.as_deref().unwrap_or("-"),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Display<'_, Vec<(String, String)>> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut table = tabular::Table::new("{:<}  {:<}");
+        for (k, v) in self.0 {
+            table.add_row(Row::new().with_cell(k).with_cell(v));
+        }
+        write!(f, "{}", table)
+    }
+}
+
+// the overview of all the commands, which is sent apart from the header to fit in a message
+pub struct Help<'a> {
+    pub prefix: &'a str,
+}
+
+impl fmt::Display for Display<'_, Help<'_>> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut table = tabular::Table::new("{:<}  -- {:<}");
+        for c in command::COMMANDS {
+            table.add_row(
+                Row::new()
+                    .with_cell(format!("{} {}", self.0.prefix, c.words()))
+                    .with_cell(Localized(Text::Description(c.kind), self.1)),
+            );
+        }
+        write!(f, "```\n{}```", table)
+    }
+}
+
+// the details of a command, with the permission required in the current scope
+pub struct CommandHelp<'a> {
+    pub prefix: &'a str,
+    pub command: &'a Command,
+    pub permission: String,
+}
+
+impl fmt::Display for Display<'_, CommandHelp<'_>> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let command = self.0.command;
+        writeln!(f, "```")?;
+        for usage in command.usages() {
+            writeln!(f, "{} {}", self.0.prefix, usage)?;
+        }
+        writeln!(f, "```")?;
+        writeln!(f, "{}", Localized(Text::Description(command.kind), self.1))?;
+        if !command.aliases.is_empty() {
+            let aliases = command.aliases.iter().map(|a| format!("`{}`", a)).join(" ");
+            writeln!(f, "{}", Localized(Text::Aliases(&aliases), self.1))?;
+        }
+        let permission = Text::RequiredPermission(&self.0.permission);
+        writeln!(f, "{}", Localized(permission, self.1))
+    }
+}
+
+impl fmt::Display for Display<'_, Error> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let text = match self.0 {
             Error::Runbot(runbot::Error::UnknownLanguageName(name)) => {
-                write!(f, "`{}` ？うーん...", name)
+                Text::UnknownLanguageName(name)
             }
             Error::Runbot(runbot::Error::UnknownCompilerName(name)) => {
-                write!(f, "`{}` っ て 何 ？ 笑", name)
+                Text::UnknownCompilerName(name)
             }
-            Error::Runbot(runbot::Error::UnknownCompilerSpec(name)) => write!(
-                f,
-                "`{}` とはなんですか？普通、`{}` とはならないとおもうのですが...",
-                name, name
-            ),
-            Error::Runbot(runbot::Error::UnmappedLanguage(name)) => {
-                write!(f, "`{}` に対応するコンパイラが決まっていない", name)
+            Error::Runbot(runbot::Error::UnknownCompilerSpec(name)) => {
+                Text::UnknownCompilerSpec(name)
             }
-            Error::Runbot(runbot::Error::NoCompilerSpecified) => {
-                write!(f, "どのコンパイラを使えばいいかわかんないよ〜")
+            Error::Runbot(runbot::Error::UnmappedLanguage(name)) => Text::UnmappedLanguage(name),
+            Error::Runbot(runbot::Error::NoCompilerSpecified) => Text::NoCompilerSpecified,
+            Error::Runbot(runbot::Error::RemapMismatch(c, l)) => Text::RemapMismatch {
+                compiler: c,
+                language: l,
+            },
+            Error::Runbot(runbot::Error::UnsupportedDocumentVersion(v)) => {
+                Text::UnsupportedDocumentVersion(v)
             }
-            Error::Runbot(runbot::Error::RemapMismatch(c, l)) => {
-                write!(f, "や、`{}` は `{}` でコンパイルできないよ", l, c)
+            Error::Runbot(runbot::Error::InvalidDocumentValue(key, value)) => {
+                Text::InvalidDocumentValue { key, value }
             }
-            Error::InvalidCodeInput(_) => write!(f, "コードの入力がおかしいよ"),
-            Error::MalformedArguments(_) => write!(f, "ちょっと、いたずらしないでください"),
-            Error::InvalidNumberOfArguments(n) => write!(f, "{}個の引数が必要だよ", n),
-            Error::UnknownCommand(c) => write!(f, "`{}`、完全に理解した", c),
-            Error::CommandIsMissing => write!(f, "？"),
-            _ => write!(f, "ごめん"),
-        }
+            Error::Runbot(runbot::Error::AccessDenied(t)) => Text::AccessDenied(t),
+            Error::Runbot(runbot::Error::RateLimited(d)) => {
+                let secs = d.as_secs() + 1;
+                return write!(
+                    f,
+                    "{}",
+                    Localized(Text::RateLimited { secs: &secs }, self.1)
+                );
+            }
+            Error::Runbot(runbot::Error::QuotaExceeded(k)) => Text::QuotaExceeded(k),
+            Error::Runbot(runbot::Error::TooManyInFlight) => Text::TooManyInFlight,
+            Error::Runbot(runbot::Error::InvalidChannelID(id)) => Text::InvalidChannelID(id),
+            Error::TOMLDeserialize(e) => Text::InvalidSettingFile(e),
+            Error::JSON(e) => Text::InvalidSettingFile(e),
+            Error::InvalidCodeInput(_) => Text::InvalidCodeInput,
+            Error::MalformedArguments(_) => Text::MalformedArguments,
+            Error::InvalidArguments(name) => {
+                let usages = match command::find(name) {
+                    Some(command) => command.usages().join("\n"),
+                    None => name.clone(),
+                };
+                let usages = format!("```\n{}\n```", usages);
+                let text = Text::InvalidArguments { usages: &usages };
+                return write!(f, "{}", Localized(text, self.1));
+            }
+            Error::UnknownCommand(c) => Text::UnknownCommand(c),
+            Error::InvalidValue(value, expected) => {
+                let expected = expected.iter().map(|x| format!("`{}`", x)).join(", ");
+                let text = Text::InvalidValue {
+                    value,
+                    expected: &expected,
+                };
+                return write!(f, "{}", Localized(text, self.1));
+            }
+            Error::GuildOnly(c) => Text::GuildOnly(c),
+            Error::CommandIsMissing => Text::CommandIsMissing,
+            Error::PermissionDenied(p) => {
+                let name = permission::permission_name(*p);
+                return write!(f, "{}", Localized(Text::PermissionDenied(&name), self.1));
+            }
+            Error::UnknownPermission(p) => Text::UnknownPermission(p),
+            Error::UnknownOutputPolicy(p) => Text::UnknownOutputPolicy(p),
+            Error::UnknownRenderStyle(s) => Text::UnknownRenderStyle(s),
+            Error::UnknownLocale(l) => Text::UnknownLocale(l),
+            Error::InvalidNumber(n) => Text::InvalidNumber(n),
+            Error::InvalidMention(m) => Text::InvalidMention(m),
+            Error::TooManyAttachments(n) => Text::TooManyAttachments(n),
+            Error::TooLargeAttachment(name) => Text::TooLargeAttachment {
+                name,
+                max_kib: &(attachment::MAX_FILE_BYTES / 1024),
+            },
+            Error::Encoding(_) => Text::Encoding,
+            Error::InvalidPrefix(p) => Text::InvalidPrefix {
+                prefix: p,
+                max_length: &prefix::MAX_PREFIX_LENGTH,
+            },
+            Error::TooLongCommandline(n) => Text::TooLongCommandline(n),
+            _ => Text::UnknownError,
+        };
+        write!(f, "{}", Localized(text, self.1))
+    }
+}
+
+pub fn stream_label(stream: Stream) -> &'static str {
+    match stream {
+        Stream::CompilerOutput | Stream::CompilerError => "compiler",
+        Stream::ProgramOutput => "stdout",
+        Stream::ProgramError => "stderr",
     }
 }
 
-fn strip_ansi_escapes(s: impl AsRef<str>) -> Result<String, fmt::Error> {
+pub(crate) fn strip_ansi_escapes(s: impl AsRef<str>) -> Result<String, fmt::Error> {
     let s = strip_ansi_escapes::strip(s.as_ref()).map_err(|_| fmt::Error)?;
     String::from_utf8(s).map_err(|_| fmt::Error)
 }
 
-impl<T> fmt::Display for Display<'_, T>
-where
-    T: CompileResult,
-{
+impl fmt::Display for Display<'_, Status> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        if let Some(msg) = self.0.compiler_message() {
-            let msg = strip_ansi_escapes(msg)?;
-            writeln!(f, "```{}```", msg)?;
-        }
+        let text = match self.0 {
+            Status::Success => Text::ExitedWithStatus(&0),
+            Status::CompileFailure(s) => Text::CompilationFailed(s),
+            Status::Failure(s) => Text::ExitedWithStatus(s),
+            Status::Signal(s) => Text::ExitedWithSignal(s),
+            Status::Timeout => Text::TimedOut,
+            Status::Unknown(Some(s)) => Text::ExitedWithUnknownStatus(s),
+            Status::Unknown(None) => Text::ExitedWithoutStatus,
+        };
+        write!(f, "{}", Localized(text, self.1))
+    }
+}
 
-        if let Some(msg) = self.0.program_message() {
-            let msg = strip_ansi_escapes(msg)?;
+impl fmt::Display for Display<'_, Outcome> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for &stream in &Stream::ALL {
+            let msg = match self.0.streams.get(stream) {
+                Some(msg) if !msg.is_empty() => strip_ansi_escapes(msg)?,
+                _ => continue,
+            };
+            if stream == Stream::ProgramError {
+                writeln!(f, "{}:", stream_label(stream))?;
+            }
             writeln!(f, "```{}```", msg)?;
         }
 
-        if let Some(s) = self.0.signal() {
-            writeln!(f, "exited with signal: {}", s)?;
-        }
-
-        if let Some(s) = self.0.status() {
-            writeln!(f, "exited with status code {}", s)?;
+        if let Some(l) = &self.0.inferred_language {
+            let language = format!("`{}`", l);
+            writeln!(
+                f,
+                "{}",
+                Localized(Text::InferredLanguage(&language), self.1)
+            )?;
         }
+        writeln!(f, "{}", Display(&self.0.status, self.1))?;
 
-        if let Some(s) = self.0.url() {
+        if let Some(s) = &self.0.url {
             writeln!(f, "{}", s)?;
         }
 
@@ -1,8 +1,9 @@
-use std::{io, result, str};
+use std::{fmt, io, result, str};
 
 use crate::code_input::ParseCodeInputError;
 
 use err_derive::Error;
+use serenity::model::permissions::Permissions;
 
 #[derive(Debug, Error)]
 pub enum Error {
@@ -12,18 +13,54 @@ pub enum Error {
     Discord(#[error(cause)] serenity::Error),
     #[error(display = "encoding error: {}", _0)]
     Encoding(#[error(cause)] str::Utf8Error),
+    #[error(display = "formatting error: {}", _0)]
+    Format(#[error(source)] fmt::Error),
     #[error(display = "IO error: {}", _0)]
     IO(#[error(source)] io::Error),
+    #[error(display = "TOML error: {}", _0)]
+    TOMLDeserialize(#[error(source)] toml::de::Error),
+    #[error(display = "TOML error: {}", _0)]
+    TOMLSerialize(#[error(source)] toml::ser::Error),
+    #[error(display = "JSON error: {}", _0)]
+    JSON(#[error(source)] serde_json::Error),
+    #[error(display = "HTTP error: {}", _0)]
+    HTTP(#[error(source)] reqwest::Error),
     #[error(display = "unable to parse code input: {}", _0)]
     InvalidCodeInput(#[error(source)] ParseCodeInputError),
     #[error(display = "malformed arguments: {}", _0)]
     MalformedArguments(#[error(source)] shell_words::ParseError),
-    #[error(display = "invalid number of arguments (expected: {})", _0)]
-    InvalidNumberOfArguments(u32),
+    #[error(display = "invalid arguments for {}", _0)]
+    InvalidArguments(String),
     #[error(display = "no command supplied")]
     CommandIsMissing,
     #[error(display = "unknown command: {}", _0)]
     UnknownCommand(String),
+    #[error(display = "invalid value {} (expected one of {:?})", _0, _1)]
+    InvalidValue(String, Vec<&'static str>),
+    #[error(display = "{} is not available in direct messages", _0)]
+    GuildOnly(String),
+    #[error(display = "permission denied (required: {:?})", _0)]
+    PermissionDenied(Permissions),
+    #[error(display = "unknown permission: {}", _0)]
+    UnknownPermission(String),
+    #[error(display = "unknown output policy: {}", _0)]
+    UnknownOutputPolicy(String),
+    #[error(display = "unknown render style: {}", _0)]
+    UnknownRenderStyle(String),
+    #[error(display = "unknown locale: {}", _0)]
+    UnknownLocale(String),
+    #[error(display = "invalid number: {}", _0)]
+    InvalidNumber(String),
+    #[error(display = "invalid mention: {}", _0)]
+    InvalidMention(String),
+    #[error(display = "too many attachments (max: {})", _0)]
+    TooManyAttachments(usize),
+    #[error(display = "too large attachment: {}", _0)]
+    TooLargeAttachment(String),
+    #[error(display = "invalid prefix: {}", _0)]
+    InvalidPrefix(String),
+    #[error(display = "too long command line (max: {})", _0)]
+    TooLongCommandline(usize),
 }
 
 impl<W> From<io::IntoInnerError<W>> for Error {
@@ -32,4 +69,14 @@ impl<W> From<io::IntoInnerError<W>> for Error {
     }
 }
 
+impl Error {
+    pub fn is_throttled(&self) -> bool {
+        matches!(
+            self,
+            Error::Runbot(runbot::Error::RateLimited(_))
+                | Error::Runbot(runbot::Error::TooManyInFlight)
+        )
+    }
+}
+
 pub type Result<T> = result::Result<T, Error>;
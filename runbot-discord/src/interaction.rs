@@ -0,0 +1,556 @@
+use runbot::Table;
+
+use super::catalog::{localize, Text};
+use super::code_input::CodeInput;
+use super::command;
+use super::error::{Error, Result};
+use super::locale::{Locale, DEFAULT_LOCALE};
+
+use serde::Deserialize;
+use serde_json::Value;
+use serenity::http::AttachmentType;
+use serenity::model::id::{ChannelId, GuildId, MessageId, RoleId, UserId};
+
+// serenity does not know interactions, so they are handled over the raw API
+const API_BASE: &str = "https://discord.com/api/v8";
+
+// custom IDs of components are limited to this length
+const MAX_CUSTOM_ID_LENGTH: usize = 100;
+
+const MAX_CHOICES: usize = 25;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Ping,
+    Command,
+    Component,
+    Autocomplete,
+    ModalSubmit,
+    Unknown,
+}
+
+impl<'de> Deserialize<'de> for Kind {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Kind, D::Error> {
+        Ok(match u8::deserialize(deserializer)? {
+            1 => Kind::Ping,
+            2 => Kind::Command,
+            3 => Kind::Component,
+            4 => Kind::Autocomplete,
+            5 => Kind::ModalSubmit,
+            _ => Kind::Unknown,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct User {
+    pub id: UserId,
+    #[serde(default)]
+    pub bot: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Member {
+    pub user: User,
+    pub roles: Vec<RoleId>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CommandOption {
+    pub name: String,
+    pub value: Option<Value>,
+    #[serde(default)]
+    pub focused: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Data {
+    pub name: Option<String>,
+    #[serde(default)]
+    pub options: Vec<CommandOption>,
+    pub custom_id: Option<String>,
+    #[serde(default)]
+    pub components: Vec<Value>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Interaction {
+    pub id: String,
+    pub application_id: String,
+    #[serde(rename = "type")]
+    pub kind: Kind,
+    pub token: String,
+    pub guild_id: Option<GuildId>,
+    pub channel_id: ChannelId,
+    // only in guilds
+    pub member: Option<Member>,
+    // only in direct messages
+    pub user: Option<User>,
+    pub data: Option<Data>,
+}
+
+impl Interaction {
+    pub fn user_id(&self) -> Option<UserId> {
+        match (&self.member, &self.user) {
+            (Some(member), _) => Some(member.user.id),
+            (None, Some(user)) => Some(user.id),
+            (None, None) => None,
+        }
+    }
+
+    pub fn option(&self, name: &str) -> Option<&Value> {
+        let data = self.data.as_ref()?;
+        let option = data.options.iter().find(|o| o.name == name)?;
+        option.value.as_ref()
+    }
+
+    fn focused_option(&self) -> Option<&CommandOption> {
+        self.data.as_ref()?.options.iter().find(|o| o.focused)
+    }
+
+    // the value of a text input in a submitted modal
+    pub fn input(&self, custom_id: &str) -> Option<&str> {
+        let data = self.data.as_ref()?;
+        data.components
+            .iter()
+            .filter_map(|row| row["components"].as_array())
+            .flatten()
+            .find(|c| c["custom_id"] == custom_id)
+            .and_then(|c| c["value"].as_str())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OptionKind {
+    String,
+    // a string which is split into multiple arguments
+    Arguments,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Completion {
+    None,
+    Language,
+    Compiler,
+}
+
+struct SlashOption {
+    name: &'static str,
+    description: Text<'static>,
+    kind: OptionKind,
+    required: bool,
+    completion: Completion,
+}
+
+// described as the text command of the same name
+struct SlashCommand {
+    name: &'static str,
+    // accepts the `scope` option, which corresponds to `global` and `my`
+    scoped: bool,
+    // asks for the code in a modal
+    code: bool,
+    // in the order of the arguments of the text command
+    options: &'static [SlashOption],
+}
+
+const LANGUAGE_OPTION: SlashOption = SlashOption {
+    name: "language",
+    description: Text::SlashLanguage,
+    kind: OptionKind::String,
+    required: true,
+    completion: Completion::Language,
+};
+
+const COMPILER_OPTION: SlashOption = SlashOption {
+    name: "compiler",
+    description: Text::SlashCompiler,
+    kind: OptionKind::String,
+    required: true,
+    completion: Completion::Compiler,
+};
+
+const RUN_OPTIONS: &[SlashOption] = &[
+    SlashOption {
+        required: false,
+        ..COMPILER_OPTION
+    },
+    SlashOption {
+        name: "options",
+        description: Text::SlashOptions,
+        kind: OptionKind::Arguments,
+        required: false,
+        completion: Completion::None,
+    },
+];
+
+const COMMANDS: &[SlashCommand] = &[
+    SlashCommand {
+        name: "help",
+        scoped: false,
+        code: false,
+        options: &[],
+    },
+    SlashCommand {
+        name: "run",
+        scoped: false,
+        code: true,
+        options: RUN_OPTIONS,
+    },
+    SlashCommand {
+        name: "run-save",
+        scoped: false,
+        code: true,
+        options: RUN_OPTIONS,
+    },
+    SlashCommand {
+        name: "list-languages",
+        scoped: false,
+        code: false,
+        options: &[],
+    },
+    SlashCommand {
+        name: "list",
+        scoped: false,
+        code: false,
+        options: &[LANGUAGE_OPTION],
+    },
+    SlashCommand {
+        name: "remap",
+        scoped: true,
+        code: false,
+        options: &[LANGUAGE_OPTION, COMPILER_OPTION],
+    },
+    SlashCommand {
+        name: "options",
+        scoped: true,
+        code: false,
+        options: &[
+            COMPILER_OPTION,
+            SlashOption {
+                name: "options",
+                description: Text::SlashOptions,
+                kind: OptionKind::Arguments,
+                required: false,
+                completion: Completion::None,
+            },
+        ],
+    },
+    SlashCommand {
+        name: "show-setting",
+        scoped: true,
+        code: false,
+        options: &[],
+    },
+    SlashCommand {
+        name: "usage",
+        scoped: false,
+        code: false,
+        options: &[],
+    },
+];
+
+fn find_command(name: &str) -> Option<&'static SlashCommand> {
+    COMMANDS.iter().find(|c| c.name == name)
+}
+
+// locales of Discord clients, which are shown the translations
+const DISCORD_LOCALES: &[(&str, Locale)] = &[
+    ("ja", Locale::Ja),
+    ("en-US", Locale::En),
+    ("en-GB", Locale::En),
+];
+
+fn localizations(text: Text) -> Value {
+    let map = DISCORD_LOCALES
+        .iter()
+        .map(|(code, locale)| (code.to_string(), Value::String(localize(text, *locale))))
+        .collect();
+    Value::Object(map)
+}
+
+// JSON definitions of the commands, to be registered to Discord
+pub fn command_definitions() -> Value {
+    let definitions: Vec<_> = COMMANDS
+        .iter()
+        .map(|command| {
+            let mut options: Vec<_> = command
+                .options
+                .iter()
+                .map(|o| {
+                    serde_json::json!({
+                        "type": 3,
+                        "name": o.name,
+                        "description": localize(o.description, DEFAULT_LOCALE),
+                        "description_localizations": localizations(o.description),
+                        "required": o.required,
+                        "autocomplete": o.completion != Completion::None,
+                    })
+                })
+                .collect();
+            if command.scoped {
+                options.push(serde_json::json!({
+                    "type": 3,
+                    "name": "scope",
+                    "description": localize(Text::SlashScope, DEFAULT_LOCALE),
+                    "description_localizations": localizations(Text::SlashScope),
+                    "required": false,
+                    "choices": [
+                        { "name": "global", "value": "global" },
+                        { "name": "my", "value": "my" },
+                    ],
+                }));
+            }
+            let (description, direct) = match command::find(command.name) {
+                Some(c) => (Text::Description(c.kind), c.direct),
+                None => (Text::UnknownError, false),
+            };
+            serde_json::json!({
+                "name": command.name,
+                "description": localize(description, DEFAULT_LOCALE),
+                "description_localizations": localizations(description),
+                "options": options,
+                "dm_permission": direct,
+            })
+        })
+        .collect();
+    Value::Array(definitions)
+}
+
+// The first line of the corresponding text command, without the prefix.
+pub fn to_commandline(interaction: &Interaction) -> Result<String> {
+    let name = interaction
+        .data
+        .as_ref()
+        .and_then(|d| d.name.as_deref())
+        .unwrap_or_default();
+    let command = find_command(name).ok_or_else(|| Error::UnknownCommand(name.to_string()))?;
+
+    let mut words = Vec::new();
+    if let Some(scope) = interaction.option("scope").and_then(Value::as_str) {
+        words.push(scope.to_string());
+    }
+    words.push(command.name.to_string());
+    for option in command.options {
+        let value = match interaction.option(option.name).and_then(Value::as_str) {
+            Some(value) => value,
+            None => continue,
+        };
+        match option.kind {
+            OptionKind::String => words.push(shell_words::quote(value).into_owned()),
+            OptionKind::Arguments => words.push(value.to_string()),
+        }
+    }
+    Ok(words.join(" "))
+}
+
+pub fn needs_code(interaction: &Interaction) -> bool {
+    let name = interaction.data.as_ref().and_then(|d| d.name.as_deref());
+    matches!(name.and_then(find_command), Some(c) if c.code)
+}
+
+// The code and stdin in a submitted modal, `None` if the interaction is not a modal submission
+pub fn code_input(interaction: &Interaction) -> Option<CodeInput> {
+    let code = interaction.input("code")?;
+    let stdin = interaction.input("stdin").filter(|s| !s.is_empty());
+    Some(CodeInput::new(code.to_string(), stdin.map(str::to_string)))
+}
+
+// Language or compiler names starting with the focused input
+pub fn complete(interaction: &Interaction, table: &Table) -> Vec<String> {
+    let name = interaction.data.as_ref().and_then(|d| d.name.as_deref());
+    let focused = match interaction.focused_option() {
+        Some(o) => o,
+        None => return Vec::new(),
+    };
+    let completion = name
+        .and_then(find_command)
+        .and_then(|c| c.options.iter().find(|o| o.name == focused.name))
+        .map(|o| o.completion)
+        .unwrap_or(Completion::None);
+    let input = focused
+        .value
+        .as_ref()
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_lowercase();
+
+    let mut candidates: Vec<String> = match completion {
+        Completion::None => Vec::new(),
+        Completion::Language => table
+            .list_languages()
+            .map(|l| l.name().as_string().clone())
+            .collect(),
+        Completion::Compiler => table
+            .compilers
+            .values()
+            .map(|c| c.name().as_string().clone())
+            .collect(),
+    };
+    candidates.retain(|c| c.to_lowercase().starts_with(&input));
+    candidates.sort();
+    candidates.truncate(MAX_CHOICES);
+    candidates
+}
+
+#[derive(Clone)]
+pub struct Client {
+    http: reqwest::blocking::Client,
+    token: String,
+}
+
+impl Client {
+    pub fn new(token: &str) -> Client {
+        Client {
+            http: reqwest::blocking::Client::new(),
+            token: format!("Bot {}", token.trim_start_matches("Bot ")),
+        }
+    }
+
+    // Overwrites all global commands
+    pub fn register_commands(&self, application_id: u64, definitions: &Value) -> Result<()> {
+        self.http
+            .put(&format!(
+                "{}/applications/{}/commands",
+                API_BASE, application_id
+            ))
+            .header("Authorization", &self.token)
+            .json(definitions)
+            .send()?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    fn callback(&self, interaction: &Interaction, response: Value) -> Result<()> {
+        self.http
+            .post(&format!(
+                "{}/interactions/{}/{}/callback",
+                API_BASE, interaction.id, interaction.token
+            ))
+            .json(&response)
+            .send()?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    // shows "thinking..." until the response is edited
+    pub fn defer(&self, interaction: &Interaction) -> Result<()> {
+        self.callback(interaction, serde_json::json!({ "type": 5 }))
+    }
+
+    pub fn respond(&self, interaction: &Interaction, content: &str) -> Result<()> {
+        self.callback(
+            interaction,
+            serde_json::json!({
+                "type": 4,
+                "data": { "content": content, "allowed_mentions": { "parse": [] } },
+            }),
+        )
+    }
+
+    pub fn respond_choices(&self, interaction: &Interaction, choices: &[String]) -> Result<()> {
+        let choices: Vec<_> = choices
+            .iter()
+            .map(|c| serde_json::json!({ "name": c, "value": c }))
+            .collect();
+        self.callback(
+            interaction,
+            serde_json::json!({ "type": 8, "data": { "choices": choices } }),
+        )
+    }
+
+    // Asks for the code and stdin. `commandline` comes back as the custom ID on submission.
+    pub fn respond_code_modal(
+        &self,
+        interaction: &Interaction,
+        commandline: &str,
+        locale: Locale,
+    ) -> Result<()> {
+        if commandline.len() > MAX_CUSTOM_ID_LENGTH {
+            return Err(Error::TooLongCommandline(MAX_CUSTOM_ID_LENGTH));
+        }
+
+        let text_input = |custom_id: &str, label: Text, required: bool| {
+            serde_json::json!({
+                "type": 1,
+                "components": [{
+                    "type": 4,
+                    "custom_id": custom_id,
+                    "label": localize(label, locale),
+                    "style": 2,
+                    "required": required,
+                }],
+            })
+        };
+        self.callback(
+            interaction,
+            serde_json::json!({
+                "type": 9,
+                "data": {
+                    "custom_id": commandline,
+                    "title": localize(Text::CodeModalTitle, locale),
+                    "components": [
+                        text_input("code", Text::CodeModalCode, true),
+                        text_input("stdin", Text::CodeModalStdin, false),
+                    ],
+                },
+            }),
+        )
+    }
+
+    fn webhook_url(&self, interaction: &Interaction) -> String {
+        format!(
+            "{}/webhooks/{}/{}",
+            API_BASE, interaction.application_id, interaction.token
+        )
+    }
+
+    // replaces the deferred response
+    pub fn edit_response(&self, interaction: &Interaction, payload: &Value) -> Result<MessageId> {
+        let message: IdOnly = self
+            .http
+            .patch(&format!(
+                "{}/messages/@original",
+                self.webhook_url(interaction)
+            ))
+            .json(payload)
+            .send()?
+            .error_for_status()?
+            .json()?;
+        Ok(message.id)
+    }
+
+    pub fn follow_up(
+        &self,
+        interaction: &Interaction,
+        payload: &Value,
+        files: Vec<AttachmentType>,
+    ) -> Result<MessageId> {
+        let request = self
+            .http
+            .post(&format!("{}?wait=true", self.webhook_url(interaction)));
+        let request = if files.is_empty() {
+            request.json(payload)
+        } else {
+            let mut form = reqwest::blocking::multipart::Form::new()
+                .text("payload_json", serde_json::to_string(payload)?);
+            for (i, file) in files.into_iter().enumerate() {
+                let (data, filename) = match file {
+                    AttachmentType::Bytes { data, filename } => (data.into_owned(), filename),
+                    _ => continue,
+                };
+                let part = reqwest::blocking::multipart::Part::bytes(data).file_name(filename);
+                form = form.part(format!("file{}", i), part);
+            }
+            request.multipart(form)
+        };
+        let message: IdOnly = request.send()?.error_for_status()?.json()?;
+        Ok(message.id)
+    }
+}
+
+#[derive(Deserialize)]
+struct IdOnly {
+    id: MessageId,
+}
@@ -2,9 +2,18 @@
 #![feature(unwrap_infallible)]
 #![feature(pattern)]
 
+pub mod attachment;
+pub mod catalog;
 pub mod code_input;
+pub mod command;
 pub mod command_context;
-pub mod compile_result;
 pub mod display;
 pub mod error;
+pub mod interaction;
+pub mod locale;
+pub mod permission;
+pub mod prefix;
+pub mod render;
+pub mod reply;
+pub mod sanitize;
 pub mod table_loader;
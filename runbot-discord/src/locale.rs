@@ -0,0 +1,39 @@
+use runbot::action;
+
+use super::error::{Error, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Locale {
+    Ja,
+    En,
+}
+
+pub const DEFAULT_LOCALE: Locale = Locale::Ja;
+
+const LOCALE_NAMES: &[(&str, Locale)] = &[("ja", Locale::Ja), ("en", Locale::En)];
+
+impl Locale {
+    pub fn parse(name: &str) -> Result<Locale> {
+        LOCALE_NAMES
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, l)| *l)
+            .ok_or_else(|| Error::UnknownLocale(name.to_string()))
+    }
+
+    pub fn name(self) -> &'static str {
+        LOCALE_NAMES
+            .iter()
+            .find(|(_, l)| *l == self)
+            .map(|(n, _)| *n)
+            .unwrap()
+    }
+}
+
+// the user's locale if set, otherwise the guild's
+pub fn locale(ctx: &runbot::Context) -> Result<Locale> {
+    match action::get_locale(ctx)? {
+        Some(name) => Ok(Locale::parse(&name).unwrap_or(DEFAULT_LOCALE)),
+        None => Ok(DEFAULT_LOCALE),
+    }
+}
@@ -0,0 +1,101 @@
+use runbot::action;
+use runbot::model::scope::Scope;
+
+use super::command::{self, Requirement};
+use super::command_context::CommandContext;
+use super::error::{Error, Result};
+
+use serenity::model::permissions::Permissions;
+
+const PERMISSION_NAMES: &[(&str, Permissions)] = &[
+    ("none", Permissions::empty()),
+    ("manage-messages", Permissions::MANAGE_MESSAGES),
+    ("manage-channels", Permissions::MANAGE_CHANNELS),
+    ("manage-roles", Permissions::MANAGE_ROLES),
+    ("manage-guild", Permissions::MANAGE_GUILD),
+    ("administrator", Permissions::ADMINISTRATOR),
+];
+
+pub fn parse_permission(name: &str) -> Result<Permissions> {
+    PERMISSION_NAMES
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, p)| *p)
+        .ok_or_else(|| Error::UnknownPermission(name.to_string()))
+}
+
+pub fn permission_name(permission: Permissions) -> String {
+    match PERMISSION_NAMES.iter().find(|(_, p)| *p == permission) {
+        Some((name, _)) => name.to_string(),
+        None => format!("{:#x}", permission.bits()),
+    }
+}
+
+fn requirement(command: &str) -> Requirement {
+    command::find(command).map_or(Requirement::None, |c| c.requirement)
+}
+
+pub fn is_configurable(command: &str) -> bool {
+    matches!(
+        requirement(command),
+        Requirement::Channel | Requirement::Guild | Requirement::Personal
+    )
+}
+
+fn default_permission(requirement: Requirement, scope: Scope) -> Permissions {
+    match (requirement, scope) {
+        (Requirement::None, _) | (Requirement::Channel, Scope::User) => Permissions::empty(),
+        (Requirement::Channel, Scope::Channel) => Permissions::MANAGE_CHANNELS,
+        (Requirement::Channel, Scope::Guild)
+        | (Requirement::Guild, _)
+        | (Requirement::Personal, _)
+        | (Requirement::Fixed, _) => Permissions::MANAGE_GUILD,
+    }
+}
+
+pub fn required_permission(ctx: &CommandContext, command: &str) -> Result<Permissions> {
+    let requirement = requirement(command);
+
+    // users can always change their own settings
+    let personal = matches!(requirement, Requirement::Channel | Requirement::Personal);
+    if ctx.scope == Scope::User && personal {
+        return Ok(Permissions::empty());
+    }
+
+    if is_configurable(command) {
+        if let Some(name) = action::get_permission(ctx, command)? {
+            return parse_permission(&name);
+        }
+    }
+
+    Ok(default_permission(requirement, ctx.scope))
+}
+
+fn member_permissions(ctx: &CommandContext) -> Permissions {
+    let guild = match ctx.guild() {
+        Some(guild) => guild,
+        None => return Permissions::empty(),
+    };
+    let guild = guild.read();
+
+    let user_id = ctx.invoker;
+    if ctx.scope == Scope::Channel {
+        guild.user_permissions_in(ctx.channel_id, user_id)
+    } else {
+        guild.member_permissions(user_id)
+    }
+}
+
+pub fn check_permission(ctx: &CommandContext, command: &str) -> Result<()> {
+    let required = required_permission(ctx, command)?;
+    if required.is_empty() {
+        return Ok(());
+    }
+
+    let permissions = member_permissions(ctx);
+    if permissions.contains(required) || permissions.administrator() {
+        Ok(())
+    } else {
+        Err(Error::PermissionDenied(required))
+    }
+}
@@ -0,0 +1,40 @@
+use runbot::action;
+
+use super::command_context::CommandContext;
+use super::error::{Error, Result};
+
+use serenity::model::id::UserId;
+
+pub const DEFAULT_PREFIX: &str = "!runbot";
+
+pub const MAX_PREFIX_LENGTH: usize = 32;
+
+pub fn prefix(ctx: &CommandContext) -> Result<String> {
+    let prefix = action::get_prefix(ctx)?;
+    Ok(prefix.unwrap_or_else(|| DEFAULT_PREFIX.to_string()))
+}
+
+pub fn validate(prefix: &str) -> Result<()> {
+    if prefix.is_empty()
+        || prefix.len() > MAX_PREFIX_LENGTH
+        || prefix.contains(char::is_whitespace)
+        || prefix.starts_with("<@")
+    {
+        Err(Error::InvalidPrefix(prefix.to_string()))
+    } else {
+        Ok(())
+    }
+}
+
+// The rest of `line` if it starts with `prefix` or a mention of the bot.
+// The prefix must be followed by a space, so that `!r` does not match `!run`.
+pub fn strip_prefix<'a>(line: &'a str, prefix: &str, bot_id: UserId) -> Option<&'a str> {
+    let mentions = [
+        format!("<@{}>", bot_id.as_u64()),
+        format!("<@!{}>", bot_id.as_u64()),
+    ];
+    std::iter::once(prefix)
+        .chain(mentions.iter().map(String::as_str))
+        .filter_map(|p| line.strip_prefix(p))
+        .find(|rest| rest.is_empty() || rest.starts_with(char::is_whitespace))
+}
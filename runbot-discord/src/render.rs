@@ -0,0 +1,350 @@
+use std::borrow::Cow;
+
+use runbot::action;
+use runbot::model::outcome::{Outcome, Status};
+use runbot::model::streams::{Stream, Streams};
+
+use super::catalog::{localize, Text};
+use super::command_context::CommandContext;
+use super::display::{stream_label, strip_ansi_escapes, Display};
+use super::error::{Error, Result};
+use super::locale::Locale;
+
+use serenity::builder::CreateEmbed;
+use serenity::http::AttachmentType;
+use serenity::utils::Colour;
+
+// each stream is kept within this, so that it fits in an embed field (limited to 1024 characters)
+const MAX_INLINE_BYTES: u64 = 800;
+
+// the streams shown share this, so that all of them fit in a message (limited to 2000 characters)
+// along with their labels and the status
+const MAX_TOTAL_INLINE_BYTES: u64 = 1400;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Limits {
+    pub lines: u64,
+    pub bytes: u64,
+}
+
+const DEFAULT_LIMITS: Limits = Limits {
+    lines: 30,
+    bytes: 800,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    Embed,
+    // plain text, for those who disabled embeds
+    Text,
+}
+
+const DEFAULT_STYLE: Style = Style::Embed;
+
+const STYLE_NAMES: &[(&str, Style)] = &[("embed", Style::Embed), ("text", Style::Text)];
+
+impl Style {
+    pub fn parse(name: &str) -> Result<Style> {
+        STYLE_NAMES
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, s)| *s)
+            .ok_or_else(|| Error::UnknownRenderStyle(name.to_string()))
+    }
+
+    pub fn name(self) -> &'static str {
+        STYLE_NAMES
+            .iter()
+            .find(|(_, s)| *s == self)
+            .map(|(n, _)| *n)
+            .unwrap()
+    }
+}
+
+pub fn style(ctx: &CommandContext) -> Result<Style> {
+    match action::get_render_style(ctx)? {
+        Some(name) => Ok(Style::parse(&name).unwrap_or(DEFAULT_STYLE)),
+        None => Ok(DEFAULT_STYLE),
+    }
+}
+
+pub const LIMIT_NAMES: &[&str] = &["lines", "bytes"];
+
+#[derive(Debug, Clone, Copy)]
+pub struct Options {
+    pub limits: Limits,
+    pub style: Style,
+    // hide compiler messages if the program is run
+    pub hide_warnings: bool,
+}
+
+pub fn options(ctx: &CommandContext) -> Result<Options> {
+    Ok(Options {
+        limits: limits(ctx)?,
+        style: style(ctx)?,
+        hide_warnings: action::get_hide_warnings(ctx)?,
+    })
+}
+
+pub fn limits(ctx: &CommandContext) -> Result<Limits> {
+    let mut limits = DEFAULT_LIMITS;
+    for (name, limit) in action::get_output_limits(ctx)? {
+        match name.as_str() {
+            "lines" => limits.lines = limit,
+            "bytes" => limits.bytes = limit.min(MAX_INLINE_BYTES),
+            _ => {}
+        }
+    }
+    Ok(limits)
+}
+
+pub fn parse_limit_name(name: &str) -> Result<&'static str> {
+    LIMIT_NAMES
+        .iter()
+        .find(|n| **n == name)
+        .copied()
+        .ok_or_else(|| Error::InvalidValue(name.to_string(), LIMIT_NAMES.to_vec()))
+}
+
+fn fits(msg: &str, limits: Limits) -> bool {
+    msg.lines().count() as u64 <= limits.lines && msg.len() as u64 <= limits.bytes
+}
+
+fn truncate(msg: &str, limits: Limits) -> String {
+    let mut truncated = String::new();
+    for line in msg.lines().take(limits.lines as usize) {
+        if (truncated.len() + line.len() + 1) as u64 > limits.bytes {
+            // a long first line is cut, so that some output is kept
+            if truncated.is_empty() {
+                let mut end = (limits.bytes as usize).saturating_sub(1);
+                while !line.is_char_boundary(end) {
+                    end -= 1;
+                }
+                if end > 0 {
+                    truncated.push_str(&line[..end]);
+                    truncated.push('\n');
+                }
+            }
+            break;
+        }
+        truncated.push_str(line);
+        truncated.push('\n');
+    }
+    truncated.push_str("...");
+    truncated
+}
+
+fn build_embed(outcome: &Outcome, attached: bool, locale: Locale) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+
+    let compiler = &outcome.compiler;
+    let title = match compiler.version() {
+        Some(v) => format!("{} {}", compiler.name(), v),
+        None => compiler.name().to_string(),
+    };
+    embed.title(title);
+    if let Some(url) = &outcome.url {
+        embed.url(url);
+    }
+
+    let colour = match outcome.status {
+        Status::Success => Colour::DARK_GREEN,
+        Status::CompileFailure(_) | Status::Failure(_) => Colour::RED,
+        Status::Signal(_) | Status::Timeout => Colour::ORANGE,
+        Status::Unknown(_) => Colour::LIGHT_GREY,
+    };
+    embed.colour(colour);
+    embed.description(Display(&outcome.status, locale));
+
+    for &stream in &Stream::ALL {
+        // empty field values are rejected by Discord
+        if let Some(msg) = outcome.streams.get(stream).filter(|m| !m.trim().is_empty()) {
+            embed.field(stream_label(stream), format!("```{}```", msg), false);
+        }
+    }
+
+    let mut footer = vec![format!("{:.2}s", outcome.elapsed.as_secs_f64())];
+    if let Some(l) = &outcome.inferred_language {
+        footer.push(localize(Text::InferredLanguage(l), locale));
+    }
+    if attached {
+        footer.push(localize(Text::OutputAttached, locale));
+    }
+    embed.footer(|f| f.text(footer.join(" · ")));
+
+    embed
+}
+
+fn filename(stream: Stream) -> &'static str {
+    match stream {
+        Stream::CompilerOutput => "compiler_stdout.txt",
+        Stream::CompilerError => "compiler.txt",
+        Stream::ProgramOutput => "stdout.txt",
+        Stream::ProgramError => "stderr.txt",
+    }
+}
+
+// the limits of each stream, when `count` streams are shown
+fn share(limits: Limits, count: usize) -> Limits {
+    let bytes = MAX_TOTAL_INLINE_BYTES / count.max(1) as u64;
+    Limits {
+        bytes: limits.bytes.min(bytes),
+        ..limits
+    }
+}
+
+// The outcome with streams truncated to be shown inline, and the whole ones to be attached
+fn inline(outcome: &Outcome, options: Options) -> Result<(Outcome, Vec<AttachmentType<'static>>)> {
+    let compiled = outcome.status.is_compiled();
+
+    let mut messages = Vec::new();
+    for &stream in &Stream::ALL {
+        if options.hide_warnings && compiled && stream.is_compiler() {
+            continue;
+        }
+
+        match outcome.streams.get(stream) {
+            Some(msg) if !msg.trim().is_empty() => {
+                messages.push((stream, strip_ansi_escapes(msg)?))
+            }
+            _ => continue,
+        }
+    }
+    let limits = share(options.limits, messages.len());
+
+    let mut files = Vec::new();
+    let mut streams = Streams::default();
+    for (stream, msg) in messages {
+        let msg = if fits(&msg, limits) {
+            msg
+        } else {
+            let truncated = truncate(&msg, limits);
+            files.push(AttachmentType::Bytes {
+                data: Cow::Owned(msg.into_bytes()),
+                filename: filename(stream).to_string(),
+            });
+            truncated
+        };
+        *streams.get_mut(stream) = Some(msg);
+    }
+    let truncated = Outcome {
+        streams,
+        ..outcome.clone()
+    };
+    Ok((truncated, files))
+}
+
+// Sends the result as is if it is short enough, otherwise sends a truncated summary
+// along with the whole output attached as files.
+pub fn send_result(ctx: &CommandContext, outcome: &Outcome, options: Options) -> Result<()> {
+    let (truncated, files) = inline(outcome, options)?;
+
+    let locale = ctx.locale();
+    match options.style {
+        Style::Embed => {
+            let embed = build_embed(&truncated, !files.is_empty(), locale);
+            ctx.send_embed(embed, files)
+        }
+        Style::Text if files.is_empty() => ctx.display(&truncated),
+        Style::Text => {
+            let content = format!(
+                "{}{}",
+                Display(&truncated, locale),
+                localize(Text::OutputAttached, locale)
+            );
+            ctx.send_files(content, files)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::Duration;
+
+    use runbot::model::compiler::{Compiler, CompilerID, CompilerName};
+    use runbot::model::language::LanguageID;
+
+    fn outcome(streams: Streams) -> Outcome {
+        let name = CompilerName::from_string("gcc-head".to_string());
+        Outcome {
+            status: Status::Failure(1),
+            compiler: Compiler::new(
+                CompilerID::from_u64(1),
+                name,
+                None,
+                LanguageID::from_u64(1),
+                "gcc-head".to_string(),
+            ),
+            inferred_language: None,
+            streams,
+            url: Some("https://wandbox.org/permlink/0123456789abcdef".to_string()),
+            elapsed: Duration::from_secs(1),
+        }
+    }
+
+    fn long(c: char) -> Option<String> {
+        Some(format!("{}\n", c.to_string().repeat(70)).repeat(100))
+    }
+
+    #[test]
+    fn four_long_streams_fit_in_a_message() {
+        let streams = Streams {
+            compiler_output: long('a'),
+            compiler_error: long('b'),
+            program_output: long('c'),
+            program_error: long('d'),
+        };
+        let options = Options {
+            limits: Limits {
+                lines: 1000,
+                bytes: MAX_INLINE_BYTES,
+            },
+            style: Style::Text,
+            hide_warnings: false,
+        };
+        let (truncated, files) = inline(&outcome(streams), options).unwrap();
+        assert_eq!(files.len(), 4);
+
+        for &locale in &[Locale::Ja, Locale::En] {
+            let content = format!(
+                "{}{}",
+                Display(&truncated, locale),
+                localize(Text::OutputAttached, locale)
+            );
+            assert!(content.chars().count() <= 2000, "{}", content.len());
+        }
+        for &stream in &Stream::ALL {
+            assert!(!truncated.streams.get(stream).unwrap().is_empty());
+        }
+    }
+
+    #[test]
+    fn single_stream_keeps_its_limit() {
+        let streams = Streams {
+            program_output: long('a'),
+            ..Streams::default()
+        };
+        let options = Options {
+            limits: DEFAULT_LIMITS,
+            style: Style::Embed,
+            hide_warnings: false,
+        };
+        let (truncated, files) = inline(&outcome(streams), options).unwrap();
+        assert_eq!(files.len(), 1);
+        let shown = truncated.streams.program_output.unwrap();
+        assert!(shown.len() as u64 <= DEFAULT_LIMITS.bytes + 3);
+        assert!(shown.len() as u64 > DEFAULT_LIMITS.bytes / 2);
+    }
+
+    #[test]
+    fn long_first_line_is_cut() {
+        let limits = Limits {
+            lines: 10,
+            bytes: 10,
+        };
+        assert_eq!(truncate(&"あ".repeat(10), limits), "あああ\n...");
+        assert_eq!(truncate(&"a".repeat(20), limits), "aaaaaaaaa\n...");
+    }
+}
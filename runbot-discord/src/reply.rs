@@ -0,0 +1,74 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+use serenity::model::id::{ChannelId, MessageId, UserId};
+
+#[derive(Debug, Clone)]
+pub struct Replies {
+    pub channel_id: ChannelId,
+    // the user who asked for the replies, usually the author of the source message
+    pub author_id: UserId,
+    pub message_ids: Vec<MessageId>,
+}
+
+struct Entry {
+    replies: Replies,
+    sent_at: Instant,
+}
+
+// Remembers which messages the bot sent in reply to which message, for a while
+#[derive(Clone)]
+pub struct ReplyTracker {
+    ttl: Duration,
+    entries: Arc<Mutex<HashMap<MessageId, Entry>>>,
+}
+
+impl ReplyTracker {
+    pub fn new(ttl: Duration) -> ReplyTracker {
+        ReplyTracker {
+            ttl,
+            entries: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub fn insert(&self, source_id: MessageId, replies: Replies) {
+        let ttl = self.ttl;
+        let mut entries = self.entries.lock();
+        entries.retain(|_, e| e.sent_at.elapsed() < ttl);
+        if replies.message_ids.is_empty() {
+            return;
+        }
+
+        let entry = Entry {
+            replies,
+            sent_at: Instant::now(),
+        };
+        entries.insert(source_id, entry);
+    }
+
+    pub fn take(&self, source_id: MessageId) -> Option<Replies> {
+        let entry = self.entries.lock().remove(&source_id)?;
+        if entry.sent_at.elapsed() < self.ttl {
+            Some(entry.replies)
+        } else {
+            None
+        }
+    }
+
+    // Forgets `reply_id` if it is a reply to a message by `author_id`.
+    pub fn take_reply(&self, reply_id: MessageId, author_id: UserId) -> bool {
+        let mut entries = self.entries.lock();
+        let entry = entries.values_mut().find(|e| {
+            e.replies.author_id == author_id && e.replies.message_ids.contains(&reply_id)
+        });
+        match entry {
+            Some(entry) => {
+                entry.replies.message_ids.retain(|id| *id != reply_id);
+                true
+            }
+            None => false,
+        }
+    }
+}
@@ -1,24 +1,52 @@
 #![feature(unwrap_infallible)]
 
+use std::borrow::Cow;
 use std::fs::File;
 use std::io::Read;
 use std::path::PathBuf;
 use std::result;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
 
 use runbot::action;
+use runbot::model::access::{AccessEntry, AccessRule, AccessTarget, Principal};
 use runbot::model::channel_id::ChannelID;
 use runbot::model::guild_id::GuildID;
+use runbot::model::language::LanguageName;
+use runbot::model::outcome::Outcome;
+use runbot::model::quota::QuotaKey;
+use runbot::model::role_id::RoleID;
+use runbot::model::scope::Scope;
+use runbot::model::setting_document::SettingDocument;
+use runbot::model::user_id::UserID;
 
+use runbot_discord::attachment;
+use runbot_discord::catalog::{localize, Text};
 use runbot_discord::code_input::CodeInput;
+use runbot_discord::command::{self, Kind};
 use runbot_discord::command_context::CommandContext;
+use runbot_discord::display::{CommandHelp, Display, Help};
 use runbot_discord::error::{Error, Result};
+use runbot_discord::interaction::{self, Interaction};
+use runbot_discord::locale::{self, Locale};
+use runbot_discord::permission;
+use runbot_discord::prefix;
+use runbot_discord::render;
+use runbot_discord::reply::ReplyTracker;
+use runbot_discord::sanitize;
 use runbot_discord::table_loader;
 
 use itertools::Itertools;
 use parking_lot::Mutex;
-use serenity::model::channel::Message;
-use serenity::model::channel::ReactionType;
+
+use serde_json::Value;
+use serenity::http::AttachmentType;
+use serenity::model::channel::{Message, Reaction, ReactionType};
+use serenity::model::event::MessageUpdateEvent;
+use serenity::model::gateway::Ready;
+use serenity::model::id::{ChannelId, GuildId, MessageId, UserId};
 use serenity::prelude::*;
 use structopt::{clap::ArgGroup, StructOpt};
 
@@ -32,43 +60,58 @@ struct RunbotHandler {
     table: runbot::Table,
     wandbox_client: wandbox::blocking::Client,
     redis_prefix: String,
+    setting_cache: runbot::SettingCache,
+    limiter: runbot::Limiter,
+    replies: ReplyTracker,
+    run_reaction: String,
+    interactions: interaction::Client,
+    // the definitions only change with the binary, so that they are registered once per process
+    commands_registered: AtomicBool,
 }
 
 impl RunbotHandler {
-    fn command_help(&self, ctx: &CommandContext) -> Result<()> {
-        ctx.say(
-            "
-`!runbot` のあとに `global` をつけると全チャンネルに対して設定ができるよ
-```
-!runbot help            -- これ
-!runbot show-setting    -- 設定表示
-!runbot auto            -- 自動実行設定
-!runbot no-auto         -- 自動実行解除
-!runbot auto-save       -- 自動実行時に保存する
-!runbot no-auto-save    -- 自動実行時に保存しない
-!runbot remap           -- 言語名とコンパイラの紐付けを上書き
-!runbot list-languages  -- 言語を一覧
-!runbot list            -- 言語に対応するコンパイラを一覧
-!runbot run             -- 実行
-!runbot run-save        -- 実行して保存
-```
-",
-        )
+    fn command_help(&self, ctx: &CommandContext, commandline: &[impl AsRef<str>]) -> Result<()> {
+        let prefix = prefix::prefix(ctx)?;
+        let name = match commandline {
+            [] => {
+                let header = Text::HelpHeader {
+                    prefix: &prefix,
+                    run_reaction: &self.run_reaction,
+                };
+                ctx.say(localize(header, ctx.locale()))?;
+                return ctx.display(&Help { prefix: &prefix });
+            }
+            [x] => x.as_ref(),
+            _ => return Err(Error::InvalidArguments("help".to_string())),
+        };
+
+        let command = command::find(name).ok_or_else(|| Error::UnknownCommand(name.to_string()))?;
+        let permission = permission::required_permission(ctx, command.name)?;
+        ctx.display(&CommandHelp {
+            prefix: &prefix,
+            command,
+            permission: permission::permission_name(permission),
+        })
     }
 
     fn command_show_setting(&self, ctx: &CommandContext) -> Result<()> {
-        let result = action::dump_setting(ctx)?;
-        ctx.display_in_code_block(&result)
+        if ctx.scope == Scope::User {
+            let result = action::dump_user_setting(ctx)?;
+            ctx.display_in_code_block(&result)
+        } else {
+            let result = action::dump_setting(ctx)?;
+            ctx.display_in_code_block(&result)
+        }
     }
 
     fn command_auto(&self, ctx: &CommandContext, state: bool) -> Result<()> {
-        action::set_auto(ctx, ctx.is_global, state)?;
+        action::set_auto(ctx, ctx.scope, state)?;
 
         ctx.react(ReactionType::Unicode("✅".to_string()))
     }
 
     fn command_auto_save(&self, ctx: &CommandContext, state: bool) -> Result<()> {
-        action::set_auto_save(ctx, ctx.is_global, state)?;
+        action::set_auto_save(ctx, ctx.scope, state)?;
 
         ctx.react(ReactionType::Unicode("✅".to_string()))
     }
@@ -79,14 +122,360 @@ impl RunbotHandler {
                 lang.as_ref().parse().into_ok(),
                 compiler.as_ref().parse().into_ok(),
             ),
-            _ => return Err(Error::InvalidNumberOfArguments(2)),
+            _ => return Err(Error::InvalidArguments("remap".to_string())),
+        };
+
+        action::remap_language(ctx, ctx.scope, lang, compiler)?;
+
+        ctx.react(ReactionType::Unicode("✅".to_string()))
+    }
+
+    fn command_options(&self, ctx: &CommandContext, commandline: &[impl AsRef<str>]) -> Result<()> {
+        let (compiler, options) = match commandline.split_first() {
+            Some((compiler, opts)) => (
+                compiler.as_ref().parse().into_ok(),
+                opts.iter().map(|o| o.as_ref().to_string()).collect(),
+            ),
+            None => return Err(Error::InvalidArguments("options".to_string())),
+        };
+
+        action::set_options(ctx, ctx.scope, compiler, options)?;
+
+        ctx.react(ReactionType::Unicode("✅".to_string()))
+    }
+
+    fn command_reset(&self, ctx: &CommandContext) -> Result<()> {
+        action::reset_user_setting(ctx)?;
+
+        ctx.react(ReactionType::Unicode("✅".to_string()))
+    }
+
+    fn command_audit(&self, ctx: &CommandContext, commandline: &[impl AsRef<str>]) -> Result<()> {
+        let count = match commandline {
+            [] => 10,
+            [x] => match x.as_ref().parse() {
+                Ok(n) => n,
+                Err(_) => return Err(Error::InvalidNumber(x.as_ref().to_string())),
+            },
+            _ => return Err(Error::InvalidArguments("audit".to_string())),
+        };
+
+        let entries = action::audit_log(ctx, ctx.scope, count)?;
+        ctx.say_to(ctx.channel_id, Display(&entries, ctx.locale()).to_string())
+    }
+
+    fn command_log_channel(
+        &self,
+        ctx: &CommandContext,
+        commandline: &[impl AsRef<str>],
+    ) -> Result<()> {
+        let channel = match commandline {
+            [x] => x.as_ref(),
+            _ => return Err(Error::InvalidArguments("log-channel".to_string())),
+        };
+
+        let id = channel.trim_start_matches("<#").trim_end_matches('>');
+        let channel_id = match id.parse() {
+            Ok(id) => ChannelID::from_u64(id),
+            Err(_) => return Err(runbot::Error::InvalidChannelID(channel.to_string()).into()),
+        };
+
+        action::set_log_channel(ctx, Some(channel_id))?;
+
+        ctx.react(ReactionType::Unicode("✅".to_string()))
+    }
+
+    fn command_no_log_channel(&self, ctx: &CommandContext) -> Result<()> {
+        action::set_log_channel(ctx, None)?;
+
+        ctx.react(ReactionType::Unicode("✅".to_string()))
+    }
+
+    fn mirror_audit_entries(&self, ctx: &CommandContext) -> Result<()> {
+        let entries = action::take_audit_entries(ctx);
+        if entries.is_empty() {
+            return Ok(());
+        }
+
+        if let Some(channel_id) = action::get_log_channel(ctx)? {
+            let message = Display(&entries, ctx.locale()).to_string();
+            ctx.say_to(channel_id.as_u64().into(), message)?;
+        }
+        Ok(())
+    }
+
+    fn command_permission(
+        &self,
+        ctx: &CommandContext,
+        commandline: &[impl AsRef<str>],
+    ) -> Result<()> {
+        let (command, permission) = match commandline {
+            [] => {
+                let permissions = action::list_permissions(ctx)?;
+                return ctx.display_in_code_block(&permissions);
+            }
+            [command] => {
+                let required = permission::required_permission(ctx, command.as_ref())?;
+                return ctx.say(format!("`{}`", permission::permission_name(required)));
+            }
+            [command, permission] => (command.as_ref(), permission.as_ref()),
+            _ => return Err(Error::InvalidArguments("permission".to_string())),
+        };
+
+        // the setting is keyed by the name, not an alias
+        let command = match command::find(command) {
+            Some(c) if permission::is_configurable(c.name) => c.name,
+            _ => return Err(Error::UnknownCommand(command.to_string())),
         };
 
-        action::remap_language(ctx, ctx.is_global, lang, compiler)?;
+        if permission == "default" {
+            action::set_permission(ctx, command, None)?;
+        } else {
+            let name = permission::permission_name(permission::parse_permission(permission)?);
+            action::set_permission(ctx, command, Some(&name))?;
+        }
 
         ctx.react(ReactionType::Unicode("✅".to_string()))
     }
 
+    fn command_access(&self, ctx: &CommandContext, commandline: &[impl AsRef<str>]) -> Result<()> {
+        let (action, target, mention) = match commandline {
+            [] => {
+                let entries = action::list_access(ctx)?;
+                return ctx.display_in_code_block(&entries);
+            }
+            [action, target, mention] => (action.as_ref(), target.as_ref(), mention.as_ref()),
+            _ => return Err(Error::InvalidArguments("access".to_string())),
+        };
+
+        let target: AccessTarget = target.parse().map_err(|_| {
+            let names = AccessTarget::ALL.iter().map(|t| t.name()).collect();
+            Error::InvalidValue(target.to_string(), names)
+        })?;
+        let principal = parse_principal(mention)?;
+
+        let rule = match action {
+            "allow" => AccessRule::Allow,
+            "deny" => AccessRule::Deny,
+            "clear" => {
+                for &rule in &[AccessRule::Allow, AccessRule::Deny] {
+                    let entry = AccessEntry {
+                        target,
+                        rule,
+                        principal,
+                    };
+                    action::set_access(ctx, entry, false)?;
+                }
+                return ctx.react(ReactionType::Unicode("✅".to_string()));
+            }
+            _ => {
+                let actions = vec!["allow", "deny", "clear"];
+                return Err(Error::InvalidValue(action.to_string(), actions));
+            }
+        };
+
+        let entry = AccessEntry {
+            target,
+            rule,
+            principal,
+        };
+        action::set_access(ctx, entry, true)?;
+
+        ctx.react(ReactionType::Unicode("✅".to_string()))
+    }
+
+    fn command_quota(&self, ctx: &CommandContext, commandline: &[impl AsRef<str>]) -> Result<()> {
+        let (key, limit) = match commandline {
+            [key, limit] => (key.as_ref(), limit.as_ref()),
+            _ => return Err(Error::InvalidArguments("quota".to_string())),
+        };
+
+        let key: QuotaKey = key.parse().map_err(|_| {
+            let names = QuotaKey::ALL.iter().map(|k| k.name()).collect();
+            Error::InvalidValue(key.to_string(), names)
+        })?;
+        let limit = match limit {
+            "none" => None,
+            n => Some(n.parse().map_err(|_| Error::InvalidNumber(n.to_string()))?),
+        };
+
+        action::set_quota(ctx, ctx.scope, key, limit)?;
+
+        ctx.react(ReactionType::Unicode("✅".to_string()))
+    }
+
+    fn command_usage(&self, ctx: &CommandContext) -> Result<()> {
+        let usage = action::usage(ctx)?;
+        ctx.display_in_code_block(&usage)
+    }
+
+    fn command_output_policy(
+        &self,
+        ctx: &CommandContext,
+        commandline: &[impl AsRef<str>],
+    ) -> Result<()> {
+        let policy = match commandline {
+            [] => return ctx.say(format!("`{}`", sanitize::policy(ctx)?.name())),
+            [x] => x.as_ref(),
+            _ => return Err(Error::InvalidArguments("output-policy".to_string())),
+        };
+
+        if policy == "default" {
+            action::set_output_policy(ctx, None)?;
+        } else {
+            let name = sanitize::Policy::parse(policy)?.name();
+            action::set_output_policy(ctx, Some(name))?;
+        }
+
+        ctx.react(ReactionType::Unicode("✅".to_string()))
+    }
+
+    fn command_output_limit(
+        &self,
+        ctx: &CommandContext,
+        commandline: &[impl AsRef<str>],
+    ) -> Result<()> {
+        let (name, limit) = match commandline {
+            [] => {
+                let limits = render::limits(ctx)?;
+                return ctx.say(format!(
+                    "lines: `{}`, bytes: `{}`",
+                    limits.lines, limits.bytes
+                ));
+            }
+            [name, limit] => (render::parse_limit_name(name.as_ref())?, limit.as_ref()),
+            _ => return Err(Error::InvalidArguments("output-limit".to_string())),
+        };
+
+        let limit = match limit {
+            "default" => None,
+            n => Some(n.parse().map_err(|_| Error::InvalidNumber(n.to_string()))?),
+        };
+        action::set_output_limit(ctx, name, limit)?;
+
+        ctx.react(ReactionType::Unicode("✅".to_string()))
+    }
+
+    fn command_render_style(
+        &self,
+        ctx: &CommandContext,
+        commandline: &[impl AsRef<str>],
+    ) -> Result<()> {
+        let style = match commandline {
+            [] => return ctx.say(format!("`{}`", render::style(ctx)?.name())),
+            [x] => x.as_ref(),
+            _ => return Err(Error::InvalidArguments("render-style".to_string())),
+        };
+
+        if style == "default" {
+            action::set_render_style(ctx, None)?;
+        } else {
+            let name = render::Style::parse(style)?.name();
+            action::set_render_style(ctx, Some(name))?;
+        }
+
+        ctx.react(ReactionType::Unicode("✅".to_string()))
+    }
+
+    fn command_prefix(&self, ctx: &CommandContext, commandline: &[impl AsRef<str>]) -> Result<()> {
+        let new_prefix = match commandline {
+            [] => return ctx.say(format!("`{}`", prefix::prefix(ctx)?)),
+            [x] => x.as_ref(),
+            _ => return Err(Error::InvalidArguments("prefix".to_string())),
+        };
+
+        if new_prefix == "default" {
+            action::set_prefix(ctx, None)?;
+        } else {
+            prefix::validate(new_prefix)?;
+            action::set_prefix(ctx, Some(new_prefix))?;
+        }
+
+        ctx.react(ReactionType::Unicode("✅".to_string()))
+    }
+
+    fn command_locale(&self, ctx: &CommandContext, commandline: &[impl AsRef<str>]) -> Result<()> {
+        let name = match commandline {
+            [] => return ctx.say(format!("`{}`", ctx.locale().name())),
+            [x] => x.as_ref(),
+            _ => return Err(Error::InvalidArguments("locale".to_string())),
+        };
+
+        if name == "default" {
+            action::set_locale(ctx, ctx.scope, None)?;
+        } else {
+            let locale = Locale::parse(name)?;
+            action::set_locale(ctx, ctx.scope, Some(locale.name()))?;
+        }
+
+        ctx.react(ReactionType::Unicode("✅".to_string()))
+    }
+
+    fn command_hide_warnings(&self, ctx: &CommandContext, hide: bool) -> Result<()> {
+        action::set_hide_warnings(ctx, hide)?;
+
+        ctx.react(ReactionType::Unicode("✅".to_string()))
+    }
+
+    fn command_export(&self, ctx: &CommandContext, commandline: &[impl AsRef<str>]) -> Result<()> {
+        let document = action::export_setting(ctx)?;
+
+        let (extension, content) = match commandline {
+            [] => ("toml", toml::to_string(&document)?),
+            [x] if x.as_ref() == "toml" => ("toml", toml::to_string(&document)?),
+            [x] if x.as_ref() == "json" => ("json", serde_json::to_string_pretty(&document)?),
+            [x] => {
+                let formats = vec!["toml", "json"];
+                return Err(Error::InvalidValue(x.as_ref().to_string(), formats));
+            }
+            _ => return Err(Error::InvalidArguments("export".to_string())),
+        };
+
+        // sent as a file, which is not split like long messages and can be imported as it is
+        let file = AttachmentType::Bytes {
+            data: Cow::Owned(content.into_bytes()),
+            filename: format!("runbot-setting.{}", extension),
+        };
+        ctx.send_files("", vec![file])
+    }
+
+    fn command_import(
+        &self,
+        ctx: &CommandContext,
+        commandline: &[impl AsRef<str>],
+        body: &str,
+    ) -> Result<()> {
+        let dry_run = match commandline {
+            [] => false,
+            [x] if x.as_ref() == "--dry-run" => true,
+            [x] => {
+                return Err(Error::InvalidValue(
+                    x.as_ref().to_string(),
+                    vec!["--dry-run"],
+                ))
+            }
+            _ => return Err(Error::InvalidArguments("import".to_string())),
+        };
+
+        // the document is either in a code block or in an attached file
+        let code = command_code_input(ctx, body)?.into_code();
+        let is_json = matches!(code.language(), Some(l) if l.as_string() == "json");
+        let document = if is_json {
+            serde_json::from_str(code.text())?
+        } else {
+            toml::from_str(code.text())?
+        };
+
+        validate_document(&document)?;
+        let result = action::import_setting(ctx, document, dry_run)?;
+        ctx.display_in_code_block(&result)?;
+
+        if result.applied {
+            ctx.react(ReactionType::Unicode("✅".to_string()))?;
+        }
+        Ok(())
+    }
+
     fn command_list_languages(&self, ctx: &CommandContext) -> Result<()> {
         let languages = action::list_languages(ctx);
         ctx.display_in_code_block(&languages)
@@ -95,7 +484,7 @@ impl RunbotHandler {
     fn command_list(&self, ctx: &CommandContext, commandline: &[impl AsRef<str>]) -> Result<()> {
         let language = match commandline {
             [x] => x.as_ref().parse().into_ok(),
-            _ => return Err(Error::InvalidNumberOfArguments(1)),
+            _ => return Err(Error::InvalidArguments("list".to_string())),
         };
 
         let compilers = action::list_compilers(ctx, language)?;
@@ -109,7 +498,21 @@ impl RunbotHandler {
         body: &str,
         save: bool,
     ) -> Result<()> {
-        let input: CodeInput = body.parse()?;
+        let target = if save {
+            AccessTarget::RunSave
+        } else {
+            AccessTarget::Run
+        };
+        action::check_access(ctx, target, &ctx.role_ids())?;
+
+        // the code can also be in the message replied to
+        let input = match command_code_input(ctx, body) {
+            Err(Error::InvalidCodeInput(e)) => match ctx.referenced_message()? {
+                Some(msg) => code_input(Some(&msg), &msg.content)?,
+                None => return Err(e.into()),
+            },
+            x => x?,
+        };
 
         let (compiler_spec, options) = match commandline.split_first() {
             Some((spec, [])) => (Some(spec.as_ref().parse().into_ok()), None),
@@ -129,101 +532,493 @@ impl RunbotHandler {
             save,
         )?;
 
-        ctx.display(&result)
+        self.send_outcome(ctx, &result)
     }
 
     fn handle_implicit(&self, ctx: &CommandContext, content: &str) -> Result<()> {
-        let input: CodeInput = match content.parse() {
+        // checked first, so that attachments are not downloaded in vain
+        if !action::is_auto(ctx)? {
+            return Ok(());
+        }
+
+        // stay silent rather than complaining on every code block
+        match action::check_access(ctx, AccessTarget::Auto, &ctx.role_ids()) {
+            Err(runbot::Error::AccessDenied(_)) => return Ok(()),
+            x => x?,
+        }
+
+        // files of unknown languages, such as notes or data, are not meant to be run
+        let attachments: Vec<_> = ctx
+            .message()
+            .map(|msg| msg.attachments.as_slice())
+            .unwrap_or_default()
+            .iter()
+            .filter(|a| attachment::is_stdin(a) || self.is_source_file(&a.filename))
+            .cloned()
+            .collect();
+
+        // attachments which cannot be a code are silently ignored as well
+        let input = match attachment::download(&attachments)
+            .and_then(|files| Ok(CodeInput::with_attachments(content, files)?))
+        {
             Err(_) => return Ok(()),
             Ok(x) => x,
         };
 
         let result = action::run_implicit(ctx, input.clone().into_code(), input.stdin().cloned())?;
 
-        use action::run_implicit::Output;
         match result {
-            Output::NoRun => Ok(()),
-            Output::Run { .. } => {
+            None => Ok(()),
+            Some(outcome) => {
                 ctx.react(ReactionType::Unicode("🆗".to_string()))?;
-                ctx.display(&result)
+                self.send_outcome(ctx, &outcome)
             }
         }
     }
 
+    fn is_source_file(&self, filename: &str) -> bool {
+        attachment::extension(filename)
+            .map(|ext| LanguageName::from_string(ext.to_string()))
+            .and_then(|ext| self.table.find_language_by_extension(&ext))
+            .is_some()
+    }
+
+    fn send_outcome(&self, ctx: &CommandContext, outcome: &Outcome) -> Result<()> {
+        let policy = sanitize::policy(ctx)?;
+        let outcome = sanitize::sanitize_outcome(outcome, policy);
+        render::send_result(ctx, &outcome, render::options(ctx)?)
+    }
+
     fn handle_explicit(&self, ctx: &mut CommandContext, line: &str, body: &str) -> Result<()> {
-        ctx.react(ReactionType::Unicode("👀".to_string()))?;
+        // interactions are already deferred, which shows that the command is being processed
+        if ctx.message().is_some() {
+            ctx.react(ReactionType::Unicode("👀".to_string()))?;
+        }
 
-        let line = if let Some(rest) = line.trim().strip_prefix("global") {
-            ctx.is_global = true;
-            rest
-        } else {
-            ctx.is_global = false;
-            line
+        let mut words = shell_words::split(line)?;
+        ctx.scope = match words.first().map(String::as_str) {
+            Some("global") => Scope::Guild,
+            Some("my") => Scope::User,
+            _ => Scope::Channel,
         };
+        if ctx.scope != Scope::Channel {
+            words.remove(0);
+        }
 
-        let words = shell_words::split(line)?;
+        // settings in direct messages are always personal
+        let direct = ctx.guild_id.is_none();
+        if direct {
+            ctx.scope = Scope::User;
+        }
 
-        let (command, commandline) = match words.split_first() {
-            Some(x) => x,
-            None => return Err(Error::CommandIsMissing),
-        };
+        let (command, commandline) = command::parse(&words, ctx.scope, direct)?;
+        permission::check_permission(ctx, command.name)?;
 
-        match command.as_ref() {
-            "help" => self.command_help(ctx),
-            "show-setting" => self.command_show_setting(ctx),
-            "auto" => self.command_auto(ctx, true),
-            "no-auto" => self.command_auto(ctx, false),
-            "auto-save" => self.command_auto_save(ctx, true),
-            "no-auto-save" => self.command_auto_save(ctx, false),
-            "remap" => self.command_remap(ctx, commandline),
-            "list-languages" => self.command_list_languages(ctx),
-            "list" => self.command_list(ctx, commandline),
-            "run" => self.command_run(ctx, commandline, body, false),
-            "run-save" => self.command_run(ctx, commandline, body, true),
-            _ => Err(Error::UnknownCommand(command.to_string())),
+        match command.kind {
+            Kind::Help => self.command_help(ctx, commandline),
+            Kind::ShowSetting => self.command_show_setting(ctx),
+            Kind::Auto => self.command_auto(ctx, true),
+            Kind::NoAuto => self.command_auto(ctx, false),
+            Kind::AutoSave => self.command_auto_save(ctx, true),
+            Kind::NoAutoSave => self.command_auto_save(ctx, false),
+            Kind::Remap => self.command_remap(ctx, commandline),
+            Kind::Options => self.command_options(ctx, commandline),
+            Kind::Reset => self.command_reset(ctx),
+            Kind::Audit => self.command_audit(ctx, commandline),
+            Kind::LogChannel => self.command_log_channel(ctx, commandline),
+            Kind::NoLogChannel => self.command_no_log_channel(ctx),
+            Kind::Permission => self.command_permission(ctx, commandline),
+            Kind::Access => self.command_access(ctx, commandline),
+            Kind::Quota => self.command_quota(ctx, commandline),
+            Kind::Usage => self.command_usage(ctx),
+            Kind::OutputPolicy => self.command_output_policy(ctx, commandline),
+            Kind::OutputLimit => self.command_output_limit(ctx, commandline),
+            Kind::RenderStyle => self.command_render_style(ctx, commandline),
+            Kind::Prefix => self.command_prefix(ctx, commandline),
+            Kind::Locale => self.command_locale(ctx, commandline),
+            Kind::HideWarnings => self.command_hide_warnings(ctx, true),
+            Kind::ShowWarnings => self.command_hide_warnings(ctx, false),
+            Kind::Export => self.command_export(ctx, commandline),
+            Kind::Import => self.command_import(ctx, commandline, body),
+            Kind::ListLanguages => self.command_list_languages(ctx),
+            Kind::List => self.command_list(ctx, commandline),
+            Kind::Run => self.command_run(ctx, commandline, body, false),
+            Kind::RunSave => self.command_run(ctx, commandline, body, true),
         }
     }
 
     fn handle(&self, ctx: &mut CommandContext, content: &str) -> Result<()> {
         let mut lines = content.lines();
-        let line = lines.next().unwrap();
+        // messages with attachments may have no text
+        let line = lines.next().unwrap_or("");
         let body = lines.join("\n");
 
-        if let Some(rest) = line.strip_prefix("!runbot") {
+        let prefix = prefix::prefix(ctx)?;
+        let bot_id = ctx.ctx.cache.read().user.id;
+        if let Some(rest) = prefix::strip_prefix(line, &prefix, bot_id) {
             self.handle_explicit(ctx, rest, &body)
         } else {
             self.handle_implicit(ctx, content)
         }
     }
+
+    // runs the code in a reacted message as if `!runbot run` were issued by the reacting user
+    fn handle_reaction(&self, ctx: &mut CommandContext, content: &str) -> Result<()> {
+        let has_attachments = matches!(ctx.message(), Some(m) if !m.attachments.is_empty());
+        if content.parse::<CodeInput>().is_err() && !has_attachments {
+            return Ok(());
+        }
+
+        self.command_run(ctx, &[] as &[&str], content, false)
+    }
 }
 
-impl EventHandler for RunbotHandler {
-    fn message(&self, ctx: Context, msg: Message) {
-        if msg.author.bot {
-            return;
+#[derive(Debug, Clone, Copy)]
+enum Trigger {
+    Message,
+    Reaction(UserId),
+}
+
+// variation selectors are not always present in reactions
+fn is_emoji(reaction: &ReactionType, emoji: &str) -> bool {
+    match reaction {
+        ReactionType::Unicode(e) => {
+            e.trim_end_matches('\u{fe0f}') == emoji.trim_end_matches('\u{fe0f}')
         }
+        _ => false,
+    }
+}
 
-        let guild_id = match msg.guild_id {
-            Some(id) => GuildID::from_u64(*id.as_u64()),
-            None => return,
-        };
-        let channel_id = ChannelID::from_u64(*msg.channel_id.as_u64());
-        let msg_content = msg.content.clone();
+// the code submitted in a modal, or the code in `body` of the message
+fn command_code_input(ctx: &CommandContext, body: &str) -> Result<CodeInput> {
+    match ctx.interaction().and_then(interaction::code_input) {
+        Some(input) => Ok(input),
+        None => code_input(ctx.message(), body),
+    }
+}
+
+// code blocks in `content` along with the files attached to `msg`
+fn code_input(msg: Option<&Message>, content: &str) -> Result<CodeInput> {
+    let files = match msg {
+        Some(msg) => attachment::download(&msg.attachments)?,
+        None => Vec::new(),
+    };
+    Ok(CodeInput::with_attachments(content, files)?)
+}
+
+fn parse_principal(mention: &str) -> Result<Principal> {
+    let invalid = || Error::InvalidMention(mention.to_string());
+
+    let inner = mention
+        .strip_prefix("<@")
+        .and_then(|s| s.strip_suffix('>'))
+        .ok_or_else(invalid)?;
+    if let Some(id) = inner.strip_prefix('&') {
+        let id = id.parse().map_err(|_| invalid())?;
+        Ok(Principal::Role(RoleID::from_u64(id)))
+    } else {
+        let id = inner
+            .trim_start_matches('!')
+            .parse()
+            .map_err(|_| invalid())?;
+        Ok(Principal::User(UserID::from_u64(id)))
+    }
+}
 
+// Values interpreted by this frontend are checked here, before anything is imported
+fn validate_document(document: &SettingDocument) -> Result<()> {
+    if let Some(policy) = &document.output_policy {
+        sanitize::Policy::parse(policy)?;
+    }
+    if let Some(style) = &document.render_style {
+        render::Style::parse(style)?;
+    }
+    if let Some(prefix) = &document.prefix {
+        prefix::validate(prefix)?;
+    }
+    if let Some(locale) = &document.locale {
+        Locale::parse(locale)?;
+    }
+    for (command, permission) in &document.permission {
+        match command::find(command) {
+            Some(c) if c.name == command && permission::is_configurable(c.name) => {}
+            _ => return Err(Error::UnknownCommand(command.clone())),
+        }
+        permission::parse_permission(permission)?;
+    }
+    for name in document.output_limit.keys() {
+        render::parse_limit_name(name)?;
+    }
+    Ok(())
+}
+
+fn report_error(ctx: &CommandContext, e: Error) {
+    if e.is_throttled() {
+        let _ = ctx.react(ReactionType::Unicode("🐢".to_string()));
+    }
+    let _ = ctx.display(&e);
+    eprintln!("command returned an error: {}", e);
+}
+
+impl RunbotHandler {
+    fn runbot_context(
+        &self,
+        ctx: &Context,
+        guild_id: Option<GuildId>,
+        channel_id: ChannelId,
+        user_id: UserId,
+    ) -> runbot::Context {
         let redis_conn = ctx.data.read().get::<ConnectionKey>().unwrap().clone();
-        let runbot_ctx = runbot::Context::new(
-            guild_id,
-            channel_id,
+        runbot::Context::new(
+            guild_id.map(|id| GuildID::from_u64(*id.as_u64())),
+            ChannelID::from_u64(*channel_id.as_u64()),
+            UserID::from_u64(*user_id.as_u64()),
             self.wandbox_client.clone(),
             redis_conn,
             self.redis_prefix.clone(),
+            self.setting_cache.clone(),
+            self.limiter.clone(),
             self.table.clone(),
-        );
+        )
+    }
 
+    fn delete_replies(&self, ctx: &Context, source_id: MessageId) {
+        let replies = match self.replies.take(source_id) {
+            Some(replies) => replies,
+            None => return,
+        };
+
+        for id in replies.message_ids {
+            if let Err(e) = replies.channel_id.delete_message(&ctx.http, id) {
+                eprintln!("unable to delete a reply: {}", e);
+            }
+        }
+    }
+
+    // `stale_replies` are the replies to the previous version of the message, if edited
+    fn process(&self, ctx: Context, msg: Message, trigger: Trigger, stale_replies: Vec<MessageId>) {
+        let invoker = match trigger {
+            Trigger::Message => msg.author.id,
+            Trigger::Reaction(user_id) => user_id,
+        };
+        let msg_content = msg.content.clone();
+        let message_id = msg.id;
+
+        let runbot_ctx = self.runbot_context(&ctx, msg.guild_id, msg.channel_id, invoker);
         let mut command_ctx = CommandContext::new(ctx, msg, runbot_ctx);
-        if let Err(e) = self.handle(&mut command_ctx, &msg_content) {
-            let _ = command_ctx.display(&e);
-            eprintln!("command returned an error: {}", e);
+        command_ctx.invoker = invoker;
+        command_ctx.reuse_replies(stale_replies);
+        let result = match trigger {
+            Trigger::Message => self.handle(&mut command_ctx, &msg_content),
+            Trigger::Reaction(_) => self.handle_reaction(&mut command_ctx, &msg_content),
+        };
+        if let Err(e) = result {
+            report_error(&command_ctx, e);
+        }
+
+        if let Err(e) = command_ctx.delete_stale_replies() {
+            eprintln!("unable to delete stale replies: {}", e);
+        }
+        self.replies.insert(message_id, command_ctx.replies());
+
+        if let Err(e) = self.mirror_audit_entries(&command_ctx) {
+            eprintln!("unable to mirror audit entries: {}", e);
+        }
+    }
+}
+
+impl RunbotHandler {
+    // Slash commands are translated into the text commands. Commands taking code ask for it
+    // in a modal first, and run on its submission.
+    fn process_interaction(&self, ctx: Context, interaction: Interaction) -> Result<()> {
+        let client = &self.interactions;
+        if interaction.kind == interaction::Kind::Autocomplete {
+            let choices = interaction::complete(&interaction, &self.table);
+            return client.respond_choices(&interaction, &choices);
+        }
+
+        let user_id = match interaction.user_id() {
+            Some(user_id) => user_id,
+            None => return Ok(()),
+        };
+        let runbot_ctx =
+            self.runbot_context(&ctx, interaction.guild_id, interaction.channel_id, user_id);
+
+        match interaction.kind {
+            interaction::Kind::Command if interaction::needs_code(&interaction) => {
+                let locale = locale::locale(&runbot_ctx)?;
+                let commandline = interaction::to_commandline(&interaction)?;
+                if let Err(e) = client.respond_code_modal(&interaction, &commandline, locale) {
+                    client.respond(&interaction, &Display(&e, locale).to_string())?;
+                }
+                return Ok(());
+            }
+            interaction::Kind::Command | interaction::Kind::ModalSubmit => {}
+            _ => return Ok(()),
+        }
+
+        // the code in a submitted modal is taken from the interaction itself
+        let line = if interaction.kind == interaction::Kind::ModalSubmit {
+            let data = interaction.data.as_ref();
+            data.and_then(|d| d.custom_id.clone()).unwrap_or_default()
+        } else {
+            interaction::to_commandline(&interaction)?
+        };
+
+        client.defer(&interaction)?;
+
+        let mut command_ctx =
+            CommandContext::from_interaction(ctx, interaction, user_id, client.clone(), runbot_ctx);
+        if let Err(e) = self.handle_explicit(&mut command_ctx, &line, "") {
+            report_error(&command_ctx, e);
+        }
+
+        if let Err(e) = self.mirror_audit_entries(&command_ctx) {
+            eprintln!("unable to mirror audit entries: {}", e);
+        }
+        Ok(())
+    }
+}
+
+impl EventHandler for RunbotHandler {
+    // called on every reconnection as well
+    fn ready(&self, _ctx: Context, ready: Ready) {
+        if self.commands_registered.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        let definitions = interaction::command_definitions();
+        if let Err(e) = self
+            .interactions
+            .register_commands(*ready.user.id.as_u64(), &definitions)
+        {
+            eprintln!("unable to register slash commands: {}", e);
+            // retried on the next reconnection
+            self.commands_registered.store(false, Ordering::SeqCst);
+        }
+    }
+
+    fn unknown(&self, ctx: Context, name: String, raw: Value) {
+        if name != "INTERACTION_CREATE" {
+            return;
+        }
+
+        let interaction = match serde_json::from_value(raw) {
+            Ok(interaction) => interaction,
+            Err(e) => {
+                eprintln!("unable to parse an interaction: {}", e);
+                return;
+            }
+        };
+        if let Err(e) = self.process_interaction(ctx, interaction) {
+            eprintln!("unable to handle an interaction: {}", e);
+        }
+    }
+
+    fn message(&self, ctx: Context, msg: Message) {
+        if msg.author.bot {
+            return;
+        }
+
+        self.process(ctx, msg, Trigger::Message, Vec::new());
+    }
+
+    // only messages which got replies are re-run, and the replies are edited in place
+    fn message_update(
+        &self,
+        ctx: Context,
+        _old: Option<Message>,
+        new: Option<Message>,
+        event: MessageUpdateEvent,
+    ) {
+        // updates without content are e.g. link previews being attached
+        if event.content.is_none() {
+            return;
+        }
+
+        let replies = match self.replies.take(event.id) {
+            Some(replies) => replies,
+            None => return,
+        };
+
+        let mut msg = match new {
+            Some(msg) => msg,
+            None => match event.channel_id.message(&ctx.http, event.id) {
+                Ok(msg) => msg,
+                Err(e) => {
+                    eprintln!("unable to fetch the edited message: {}", e);
+                    return;
+                }
+            },
+        };
+        // messages fetched over HTTP do not carry the guild, which tells them from direct messages
+        msg.guild_id = msg.guild_id.or(event.guild_id);
+
+        self.process(ctx, msg, Trigger::Message, replies.message_ids);
+    }
+
+    fn message_delete(&self, ctx: Context, _channel_id: ChannelId, message_id: MessageId) {
+        self.delete_replies(&ctx, message_id);
+    }
+
+    fn message_delete_bulk(
+        &self,
+        ctx: Context,
+        _channel_id: ChannelId,
+        message_ids: Vec<MessageId>,
+    ) {
+        for message_id in message_ids {
+            self.delete_replies(&ctx, message_id);
+        }
+    }
+
+    fn reaction_add(&self, ctx: Context, reaction: Reaction) {
+        if reaction.user_id == ctx.cache.read().user.id {
+            return;
+        }
+
+        if is_emoji(&reaction.emoji, &self.run_reaction) {
+            self.run_reacted(ctx, reaction);
+        } else if is_emoji(&reaction.emoji, "🗑") {
+            self.delete_reply(ctx, reaction);
+        }
+    }
+}
+
+impl RunbotHandler {
+    fn run_reacted(&self, ctx: Context, reaction: Reaction) {
+        let mut msg = match reaction.message(&ctx.http) {
+            Ok(msg) => msg,
+            Err(e) => {
+                eprintln!("unable to fetch the reacted message: {}", e);
+                return;
+            }
+        };
+        msg.guild_id = msg.guild_id.or(reaction.guild_id);
+        if msg.author.bot {
+            return;
+        }
+
+        // reacting again re-runs the code, editing the previous replies in place
+        let stale_replies = match self.replies.take(msg.id) {
+            Some(replies) => replies.message_ids,
+            None => Vec::new(),
+        };
+        self.process(ctx, msg, Trigger::Reaction(reaction.user_id), stale_replies);
+    }
+
+    fn delete_reply(&self, ctx: Context, reaction: Reaction) {
+        // only the author of the source message can delete replies
+        if self
+            .replies
+            .take_reply(reaction.message_id, reaction.user_id)
+        {
+            if let Err(e) = reaction
+                .channel_id
+                .delete_message(&ctx.http, reaction.message_id)
+            {
+                eprintln!("unable to delete a reply: {}", e);
+            }
         }
     }
 }
@@ -258,6 +1053,31 @@ struct Opt {
         default_value = "https://wandbox.org/api/"
     )]
     wandbox_home: String,
+    #[structopt(long, env = "RUNBOT_SETTING_CACHE_TTL", default_value = "60")]
+    setting_cache_ttl: u64,
+    /// Invalidate setting cache on writes from other instances via keyspace notifications
+    #[structopt(long, env = "RUNBOT_WATCH_KEYSPACE")]
+    watch_keyspace: bool,
+    /// Seconds to remember replies, to edit or delete them along with the source message
+    #[structopt(long, env = "RUNBOT_REPLY_TRACKING_TTL", default_value = "3600")]
+    reply_tracking_ttl: u64,
+    /// Executions allowed per user, in the form of COUNT/SECONDS
+    #[structopt(long, env = "RUNBOT_USER_RATE_LIMIT", default_value = "5/60")]
+    user_rate_limit: runbot::RateLimit,
+    /// Executions allowed per channel, in the form of COUNT/SECONDS
+    #[structopt(long, env = "RUNBOT_CHANNEL_RATE_LIMIT", default_value = "20/60")]
+    channel_rate_limit: runbot::RateLimit,
+    /// Executions allowed per guild, in the form of COUNT/SECONDS
+    #[structopt(long, env = "RUNBOT_GUILD_RATE_LIMIT", default_value = "60/60")]
+    guild_rate_limit: runbot::RateLimit,
+    /// Executions allowed per user in direct messages, in the form of COUNT/SECONDS
+    #[structopt(long, env = "RUNBOT_DIRECT_RATE_LIMIT", default_value = "3/60")]
+    direct_rate_limit: runbot::RateLimit,
+    /// Maximum number of executions running at the same time
+    #[structopt(long, env = "RUNBOT_MAX_IN_FLIGHT", default_value = "8")]
+    max_in_flight: usize,
+    #[structopt(long, env = "RUNBOT_RUN_REACTION", default_value = "▶️")]
+    run_reaction: String,
 }
 
 fn main() -> result::Result<(), Box<dyn std::error::Error>> {
@@ -276,18 +1096,49 @@ fn main() -> result::Result<(), Box<dyn std::error::Error>> {
     let table = table_loader::load_table(opt.table_path)?;
     let wandbox_client = wandbox::blocking::Client::new(&opt.wandbox_home)?;
 
+    let redis_client = redis::Client::open(opt.redis_uri)?;
+    let mut redis_conn = redis_client.get_connection()?;
+
+    for description in runbot::migration::migrate(&mut redis_conn, &opt.redis_prefix)? {
+        eprintln!("applied migration: {}", description);
+    }
+
+    let redis_conn = Arc::new(Mutex::new(redis_conn));
+
+    let setting_cache = runbot::SettingCache::new(Duration::from_secs(opt.setting_cache_ttl));
+    if opt.watch_keyspace {
+        let cache = setting_cache.clone();
+        let prefix = opt.redis_prefix.clone();
+        thread::spawn(move || {
+            if let Err(e) = cache.watch(&redis_client, &prefix) {
+                eprintln!("keyspace watcher stopped: {}", e);
+            }
+        });
+    }
+
+    let limiter = runbot::Limiter::new(runbot::Limits {
+        user: Some(opt.user_rate_limit),
+        channel: Some(opt.channel_rate_limit),
+        guild: Some(opt.guild_rate_limit),
+        direct: Some(opt.direct_rate_limit),
+        max_in_flight: Some(opt.max_in_flight),
+    });
+
     let mut client = Client::new(
         token,
         RunbotHandler {
             table,
             wandbox_client,
             redis_prefix: opt.redis_prefix,
+            setting_cache,
+            limiter,
+            replies: ReplyTracker::new(Duration::from_secs(opt.reply_tracking_ttl)),
+            run_reaction: opt.run_reaction,
+            interactions: interaction::Client::new(token),
+            commands_registered: AtomicBool::new(false),
         },
     )?;
 
-    let redis_client = redis::Client::open(opt.redis_uri)?;
-    let redis_conn = Arc::new(Mutex::new(redis_client.get_connection()?));
-
     {
         let mut data = client.data.write();
         data.insert::<ConnectionKey>(redis_conn);
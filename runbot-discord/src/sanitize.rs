@@ -0,0 +1,83 @@
+use runbot::action;
+use runbot::model::outcome::Outcome;
+
+use super::command_context::CommandContext;
+use super::error::{Error, Result};
+
+// zero width space, to break the syntax without visible changes
+const BREAK: &str = "\u{200b}";
+
+const INVITE_HOSTS: &[&str] = &[
+    "discord.gg/",
+    "discord.com/invite/",
+    "discordapp.com/invite/",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Policy {
+    // neutralize mentions and invite links
+    Strict,
+    // neutralize mentions only
+    Mentions,
+    // leave the output as is (nobody is pinged anyway)
+    Off,
+}
+
+const DEFAULT_POLICY: Policy = Policy::Strict;
+
+const POLICY_NAMES: &[(&str, Policy)] = &[
+    ("strict", Policy::Strict),
+    ("mentions", Policy::Mentions),
+    ("off", Policy::Off),
+];
+
+impl Policy {
+    pub fn parse(name: &str) -> Result<Policy> {
+        POLICY_NAMES
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, p)| *p)
+            .ok_or_else(|| Error::UnknownOutputPolicy(name.to_string()))
+    }
+
+    pub fn name(self) -> &'static str {
+        POLICY_NAMES
+            .iter()
+            .find(|(_, p)| *p == self)
+            .map(|(n, _)| *n)
+            .unwrap()
+    }
+}
+
+pub fn policy(ctx: &CommandContext) -> Result<Policy> {
+    match action::get_output_policy(ctx)? {
+        // unknown names (from older versions) fall back to the safest one
+        Some(name) => Ok(Policy::parse(&name).unwrap_or(DEFAULT_POLICY)),
+        None => Ok(DEFAULT_POLICY),
+    }
+}
+
+pub fn sanitize(s: &str, policy: Policy) -> String {
+    let mut s = s.to_string();
+    if policy == Policy::Off {
+        return s;
+    }
+
+    // covers `@everyone`, `@here` and `<@id>`, `<@!id>`, `<@&id>`
+    s = s.replace('@', &format!("@{}", BREAK));
+
+    if policy == Policy::Strict {
+        for host in INVITE_HOSTS {
+            let (name, rest) = host.split_at(host.find('.').unwrap());
+            s = s.replace(host, &format!("{}{}{}", name, BREAK, rest));
+        }
+    }
+    s
+}
+
+pub fn sanitize_outcome(outcome: &Outcome, policy: Policy) -> Outcome {
+    Outcome {
+        streams: outcome.streams.map(|_, m| sanitize(m, policy)),
+        ..outcome.clone()
+    }
+}
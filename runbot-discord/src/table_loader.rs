@@ -4,7 +4,7 @@ use std::path::Path;
 use std::{fs, io};
 
 use runbot::model::compiler::{Compiler, CompilerID, CompilerName, CompilerVersion};
-use runbot::model::language::{Language, LanguageID, LanguageName};
+use runbot::model::language::{Heuristics, Language, LanguageID, LanguageName};
 use runbot::Table;
 
 use err_derive::Error;
@@ -18,9 +18,21 @@ struct TableFile {
 #[derive(Deserialize)]
 struct LanguageData {
     aliases: Vec<String>,
+    #[serde(default)]
+    extensions: Vec<String>,
+    #[serde(default)]
+    heuristics: HeuristicsData,
     compilers: HashMap<String, CompilerData>,
 }
 
+#[derive(Deserialize, Default)]
+struct HeuristicsData {
+    #[serde(default)]
+    shebangs: Vec<String>,
+    #[serde(default)]
+    keywords: Vec<String>,
+}
+
 #[derive(Deserialize)]
 #[serde(rename_all = "kebab-case")]
 struct CompilerData {
@@ -79,7 +91,23 @@ fn to_table(table_file: TableFile) -> Result<Table, Error> {
             .into_iter()
             .map(LanguageName::from_string)
             .collect();
-        let language = Language::new(language_id, language_name, names, default_compiler);
+        let extensions = language_data
+            .extensions
+            .into_iter()
+            .map(LanguageName::from_string)
+            .collect();
+        let heuristics = Heuristics::new(
+            language_data.heuristics.shebangs,
+            language_data.heuristics.keywords,
+        );
+        let language = Language::new(
+            language_id,
+            language_name,
+            names,
+            extensions,
+            heuristics,
+            default_compiler,
+        );
         languages.insert(language_id, language);
     }
 
@@ -1,17 +1,48 @@
+pub mod access;
+pub mod audit_log;
 pub mod dump_setting;
+pub mod dump_user_setting;
+pub mod export_setting;
+pub mod import_setting;
 pub mod list_compilers;
 pub mod list_languages;
 pub mod remap_language;
+pub mod reset_user_setting;
 pub mod run;
 pub mod run_implicit;
 pub mod set_auto;
 pub mod set_auto_save;
+pub mod set_locale;
+pub mod set_log_channel;
+pub mod set_options;
+pub mod set_output_policy;
+pub mod set_permission;
+pub mod set_prefix;
+pub mod set_quota;
+pub mod usage;
 
+pub use access::{check_access, list_access, set_access};
+pub use audit_log::{audit_log, take_audit_entries};
 pub use dump_setting::dump_setting;
+pub use dump_user_setting::dump_user_setting;
+pub use export_setting::export_setting;
+pub use import_setting::import_setting;
 pub use list_compilers::list_compilers;
 pub use list_languages::list_languages;
 pub use remap_language::remap_language;
+pub use reset_user_setting::reset_user_setting;
 pub use run::run;
 pub use run_implicit::run_implicit;
-pub use set_auto::set_auto;
+pub use set_auto::{is_auto, set_auto};
 pub use set_auto_save::set_auto_save;
+pub use set_locale::{get_locale, set_locale};
+pub use set_log_channel::{get_log_channel, set_log_channel};
+pub use set_options::set_options;
+pub use set_output_policy::{
+    get_hide_warnings, get_output_limits, get_output_policy, get_render_style, set_hide_warnings,
+    set_output_limit, set_output_policy, set_render_style,
+};
+pub use set_permission::{get_permission, list_permissions, set_permission};
+pub use set_prefix::{get_prefix, set_prefix};
+pub use set_quota::set_quota;
+pub use usage::usage;
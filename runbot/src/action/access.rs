@@ -0,0 +1,68 @@
+use crate::model::access::{AccessEntry, AccessRule, AccessTarget, Principal};
+use crate::model::role_id::RoleID;
+use crate::{Context, Error, Result};
+
+pub fn list_access(ctx: &Context) -> Result<Vec<AccessEntry>> {
+    let entries = ctx
+        .setting
+        .get_access_all(ctx.guild_id)?
+        .iter()
+        .filter_map(|k| AccessEntry::from_key(k))
+        .collect();
+    Ok(entries)
+}
+
+// Adding an entry replaces the opposite rule for the same principal and target.
+pub fn set_access(ctx: &Context, entry: AccessEntry, set: bool) -> Result<()> {
+    if set {
+        let opposite = AccessEntry {
+            rule: match entry.rule {
+                AccessRule::Allow => AccessRule::Deny,
+                AccessRule::Deny => AccessRule::Allow,
+            },
+            ..entry
+        };
+        let entries = list_access(ctx)?;
+        if entries.contains(&opposite) {
+            ctx.setting
+                .remove_access(ctx.guild_id, &opposite.to_key())?;
+        }
+        ctx.setting.add_access(ctx.guild_id, &entry.to_key())
+    } else {
+        ctx.setting.remove_access(ctx.guild_id, &entry.to_key())
+    }
+}
+
+// Users listed explicitly take precedence over roles, and denial over allowance.
+// When any allow entry exists for the target, only the allowed ones may proceed.
+pub fn check_access(ctx: &Context, target: AccessTarget, role_ids: &[RoleID]) -> Result<()> {
+    let entries: Vec<_> = list_access(ctx)?
+        .into_iter()
+        .filter(|e| e.target == target)
+        .collect();
+    let has = |rule, principal| {
+        entries
+            .iter()
+            .any(|e| e.rule == rule && e.principal == principal)
+    };
+    let has_role = |rule| role_ids.iter().any(|&id| has(rule, Principal::Role(id)));
+
+    let user = Principal::User(ctx.user_id);
+    let allowed = if has(AccessRule::Deny, user) {
+        false
+    } else if has(AccessRule::Allow, user) {
+        true
+    } else if has_role(AccessRule::Deny) {
+        false
+    } else if entries.iter().any(|e| e.rule == AccessRule::Allow) {
+        has_role(AccessRule::Allow)
+    } else {
+        true
+    };
+
+    if allowed {
+        Ok(())
+    } else {
+        Err(Error::AccessDenied(target))
+    }
+}
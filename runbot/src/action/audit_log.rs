@@ -0,0 +1,135 @@
+use crate::model::audit::{AuditEntry, AuditScope};
+use crate::model::compiler::CompilerID;
+use crate::model::compiler_options::CompilerOptions;
+use crate::model::language::LanguageID;
+use crate::model::scope::Scope;
+use crate::setting::Record;
+use crate::{Context, Result};
+
+use itertools::Itertools;
+
+// Recent changes visible from `scope`, newest first.
+// The channel scope includes changes of guild defaults, since they affect the channel as well.
+pub fn audit_log(ctx: &Context, scope: Scope, count: usize) -> Result<Vec<AuditEntry>> {
+    let entries = ctx
+        .setting
+        .get_audit_log(ctx.guild_id)?
+        .into_iter()
+        .filter(|r| match (scope, r.scope) {
+            (Scope::Channel, AuditScope::Channel(id)) => id == ctx.channel_id,
+            (Scope::Channel, AuditScope::Guild) => true,
+            (Scope::Guild, _) => true,
+            _ => false,
+        })
+        .take(count)
+        .map(|r| to_entry(ctx, r))
+        .collect();
+    Ok(entries)
+}
+
+// Changes made through `ctx` so far, in the order they were made.
+pub fn take_audit_entries(ctx: &Context) -> Vec<AuditEntry> {
+    ctx.setting
+        .take_recorded()
+        .into_iter()
+        .map(|r| to_entry(ctx, r))
+        .collect()
+}
+
+fn to_entry(ctx: &Context, record: Record) -> AuditEntry {
+    let parse_id = |s: &String| s.parse::<u64>().ok();
+    let language_name = |s: &String| {
+        parse_id(s)
+            .map(LanguageID::from_u64)
+            .and_then(|id| ctx.table.languages.get(&id))
+            .map_or_else(|| s.clone(), |l| l.name().to_string())
+    };
+    let compiler_name = |s: &String| {
+        parse_id(s)
+            .map(CompilerID::from_u64)
+            .and_then(|id| ctx.table.compilers.get(&id))
+            .map_or_else(|| s.clone(), |c| c.name().to_string())
+    };
+
+    let (key, old, new) = match (record.field.as_str(), &record.hash_key) {
+        ("remap", Some(k)) => (
+            format!("remap.{}", language_name(k)),
+            record.old.as_ref().map(compiler_name),
+            record.new.as_ref().map(compiler_name),
+        ),
+        ("options", Some(k)) => {
+            let options = |s: &String| CompilerOptions::from_raw(s).into_iter().join(" ");
+            (
+                format!("options.{}", compiler_name(k)),
+                record.old.as_ref().map(options),
+                record.new.as_ref().map(options),
+            )
+        }
+        ("access", Some(k)) => (
+            format!("access.{}", k),
+            record.old.as_ref().map(|_| "set".to_string()),
+            record.new.as_ref().map(|_| "set".to_string()),
+        ),
+        ("permission", Some(k)) | ("quota", Some(k)) | ("output_limit", Some(k)) => (
+            format!("{}.{}", record.field.replace('_', "-"), k),
+            record.old.clone(),
+            record.new.clone(),
+        ),
+        ("auto", None) | ("auto_save", None) | ("hide_warnings", None) => {
+            let flag = |s: &String| (s != "0").to_string();
+            (
+                record.field.replace('_', "-"),
+                record.old.as_ref().map(flag),
+                record.new.as_ref().map(flag),
+            )
+        }
+        _ => (
+            record.field.replace('_', "-"),
+            record.old.clone(),
+            record.new.clone(),
+        ),
+    };
+
+    AuditEntry {
+        actor: record.actor,
+        scope: record.scope,
+        key,
+        old,
+        new,
+        timestamp: record.timestamp,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::action::set_auto;
+    use crate::model::guild_id::GuildID;
+    use crate::testing::Namespace;
+
+    #[test]
+    #[ignore = "requires a Redis server at RUNBOT_TEST_REDIS_URI"]
+    fn guild_write_records_overwritten_channels() {
+        let namespace = Namespace::new();
+        let ctx = namespace.context(Some(GuildID::from_u64(1)), 2, 3);
+
+        set_auto(&ctx, Scope::Channel, false).unwrap();
+        set_auto(&ctx, Scope::Guild, true).unwrap();
+
+        let entries: Vec<_> = audit_log(&ctx, Scope::Guild, 10)
+            .unwrap()
+            .into_iter()
+            .map(|e| (e.scope, e.old, e.new))
+            .collect();
+        let channel = AuditScope::Channel(ctx.channel_id);
+        let flag = |x: bool| Some(x.to_string());
+        assert_eq!(
+            entries,
+            vec![
+                (AuditScope::Guild, None, flag(true)),
+                (channel, flag(false), flag(true)),
+                (channel, None, flag(false)),
+            ]
+        );
+    }
+}
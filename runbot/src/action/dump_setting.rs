@@ -1,4 +1,6 @@
+use crate::model::access::AccessEntry;
 use crate::model::compiler::CompilerName;
+use crate::model::compiler_options::CompilerOptions;
 use crate::model::language::LanguageName;
 use crate::{Context, Result};
 
@@ -6,20 +8,30 @@ pub struct Output {
     pub auto: bool,
     pub auto_save: bool,
     pub remap: Vec<(LanguageName, CompilerName)>,
+    pub options: Vec<(CompilerName, CompilerOptions)>,
+    pub access: Vec<AccessEntry>,
 }
 
 pub fn dump_setting(ctx: &Context) -> Result<Output> {
-    let auto = ctx.is_auto()?;
-    let auto_save = ctx.is_auto_save()?;
+    let auto = ctx.is_channel_auto()?;
+    let auto_save = ctx.is_channel_auto_save()?;
     let remap = ctx
         .all_remap()?
         .into_iter()
         .map(|(l, c)| (l.clone(), c.clone()))
         .collect();
+    let options = ctx
+        .all_options()?
+        .into_iter()
+        .map(|(c, o)| (c.clone(), o))
+        .collect();
+    let access = super::list_access(ctx)?;
 
     Ok(Output {
         auto,
         auto_save,
         remap,
+        options,
+        access,
     })
 }
@@ -0,0 +1,48 @@
+use crate::model::compiler::CompilerName;
+use crate::model::compiler_options::CompilerOptions;
+use crate::model::language::LanguageName;
+use crate::{Context, Result};
+
+pub struct Output {
+    pub auto: Option<bool>,
+    pub auto_save: Option<bool>,
+    pub locale: Option<String>,
+    pub remap: Vec<(LanguageName, CompilerName)>,
+    pub options: Vec<(CompilerName, CompilerOptions)>,
+}
+
+pub fn dump_user_setting(ctx: &Context) -> Result<Output> {
+    let auto = ctx.setting.get_user_auto(ctx.personal_scope())?;
+    let auto_save = ctx.setting.get_user_auto_save(ctx.personal_scope())?;
+    let locale = ctx.setting.get_user_locale(ctx.personal_scope())?;
+    let remap = ctx
+        .setting
+        .get_user_remap_all(ctx.personal_scope())?
+        .into_iter()
+        .map(|(l, c)| {
+            (
+                ctx.table.get_language(l).name().clone(),
+                ctx.table.get_compiler(c).name().clone(),
+            )
+        })
+        .collect();
+    let options = ctx
+        .setting
+        .get_user_options_all(ctx.personal_scope())?
+        .into_iter()
+        .map(|(c, o)| {
+            (
+                ctx.table.get_compiler(c).name().clone(),
+                CompilerOptions::from_raw(&o),
+            )
+        })
+        .collect();
+
+    Ok(Output {
+        auto,
+        auto_save,
+        locale,
+        remap,
+        options,
+    })
+}
@@ -0,0 +1,87 @@
+use std::collections::BTreeMap;
+
+use crate::model::compiler_options::CompilerOptions;
+use crate::model::setting_document::{ScopeSetting, SettingDocument};
+use crate::setting::Scope;
+use crate::{Context, Result};
+
+pub fn export_setting(ctx: &Context) -> Result<SettingDocument> {
+    let guild = export_scope(ctx, Scope::Guild)?;
+
+    let mut channels = BTreeMap::new();
+    for channel_id in ctx.setting.list_channels(ctx.guild_id)? {
+        let setting = export_scope(ctx, Scope::Channel(channel_id))?;
+        if !setting.is_empty() {
+            channels.insert(channel_id.as_u64().to_string(), setting);
+        }
+    }
+
+    let mut access = ctx.setting.get_access_all(ctx.guild_id)?;
+    access.sort();
+
+    Ok(SettingDocument {
+        version: SettingDocument::VERSION,
+        log_channel: ctx
+            .setting
+            .get_log_channel(ctx.guild_id)?
+            .map(|id| id.as_u64().to_string()),
+        output_policy: ctx.setting.get_output_policy(ctx.guild_id)?,
+        render_style: ctx.setting.get_render_style(ctx.guild_id)?,
+        hide_warnings: ctx.setting.get_exact_hide_warnings(ctx.guild_id)?,
+        prefix: ctx.setting.get_prefix(ctx.guild_id)?,
+        locale: ctx.setting.get_locale(ctx.guild_id)?,
+        access,
+        permission: ctx
+            .setting
+            .get_permission_all(ctx.guild_id)?
+            .into_iter()
+            .collect(),
+        output_limit: ctx
+            .setting
+            .get_output_limit_all(ctx.guild_id)?
+            .into_iter()
+            .collect(),
+        guild,
+        channels,
+    })
+}
+
+fn export_scope(ctx: &Context, scope: Scope) -> Result<ScopeSetting> {
+    let auto = ctx.setting.get_exact_auto(ctx.guild_id, scope)?;
+    let auto_save = ctx.setting.get_exact_auto_save(ctx.guild_id, scope)?;
+    let remap = ctx
+        .setting
+        .get_exact_remap_all(ctx.guild_id, scope)?
+        .into_iter()
+        .map(|(l, c)| {
+            (
+                ctx.table.get_language(l).name().to_string(),
+                ctx.table.get_compiler(c).name().to_string(),
+            )
+        })
+        .collect();
+    let options = ctx
+        .setting
+        .get_exact_options_all(ctx.guild_id, scope)?
+        .into_iter()
+        .map(|(c, o)| {
+            (
+                ctx.table.get_compiler(c).name().to_string(),
+                CompilerOptions::from_raw(&o).into_iter().collect(),
+            )
+        })
+        .collect();
+    let quota = ctx
+        .setting
+        .get_quota_all(ctx.guild_id, scope)?
+        .into_iter()
+        .collect();
+
+    Ok(ScopeSetting {
+        auto,
+        auto_save,
+        remap,
+        options,
+        quota,
+    })
+}
@@ -0,0 +1,497 @@
+use std::collections::HashMap;
+
+use crate::action::access::{list_access, set_access};
+use crate::model::access::AccessEntry;
+use crate::model::channel_id::ChannelID;
+use crate::model::compiler::{Compiler, CompilerName};
+use crate::model::compiler_options::CompilerOptions;
+use crate::model::language::{Language, LanguageName};
+use crate::model::quota::QuotaKey;
+use crate::model::setting_document::{ScopeSetting, SettingDocument};
+use crate::setting::Scope;
+use crate::{Context, Error, Result};
+
+pub struct Change {
+    // `None` for the guild defaults
+    pub channel_id: Option<ChannelID>,
+    pub key: String,
+    pub old: Option<String>,
+    pub new: String,
+}
+
+pub struct Output {
+    pub changes: Vec<Change>,
+    pub applied: bool,
+}
+
+// guild-wide settings, whose values are validated by the frontend
+struct ResolvedGuild {
+    log_channel: Option<ChannelID>,
+    output_policy: Option<String>,
+    render_style: Option<String>,
+    hide_warnings: Option<bool>,
+    prefix: Option<String>,
+    locale: Option<String>,
+    access: Vec<AccessEntry>,
+    permission: Vec<(String, String)>,
+    output_limit: Vec<(String, u64)>,
+}
+
+struct ResolvedScope<'a> {
+    scope: Scope,
+    auto: Option<bool>,
+    auto_save: Option<bool>,
+    remap: Vec<(&'a Language, &'a Compiler)>,
+    options: Vec<(&'a Compiler, CompilerOptions)>,
+    quota: Vec<(QuotaKey, u64)>,
+}
+
+pub fn import_setting(ctx: &Context, document: SettingDocument, dry_run: bool) -> Result<Output> {
+    if document.version != SettingDocument::VERSION {
+        return Err(Error::UnsupportedDocumentVersion(document.version));
+    }
+
+    // validate the whole document before writing anything
+    let guild = resolve_guild(&document)?;
+    let mut scopes = vec![resolve_scope(ctx, Scope::Guild, document.guild)?];
+    for (id, setting) in document.channels {
+        let channel_id = match id.parse() {
+            Ok(id) => ChannelID::from_u64(id),
+            Err(_) => return Err(Error::InvalidChannelID(id)),
+        };
+        scopes.push(resolve_scope(ctx, Scope::Channel(channel_id), setting)?);
+    }
+
+    let mut changes = Vec::new();
+    diff_guild(ctx, &guild, &mut changes)?;
+    for resolved in &scopes {
+        diff_scope(ctx, resolved, &mut changes)?;
+    }
+
+    if !dry_run {
+        apply_guild(ctx, &guild)?;
+        for resolved in &scopes {
+            apply_scope(ctx, resolved)?;
+        }
+    }
+
+    Ok(Output {
+        changes,
+        applied: !dry_run,
+    })
+}
+
+fn resolve_guild(document: &SettingDocument) -> Result<ResolvedGuild> {
+    let log_channel = match &document.log_channel {
+        Some(id) => match id.parse() {
+            Ok(id) => Some(ChannelID::from_u64(id)),
+            Err(_) => return Err(Error::InvalidChannelID(id.clone())),
+        },
+        None => None,
+    };
+
+    let mut access = Vec::new();
+    for key in &document.access {
+        match AccessEntry::from_key(key) {
+            Some(entry) => access.push(entry),
+            None => {
+                return Err(Error::InvalidDocumentValue(
+                    "access".to_string(),
+                    key.clone(),
+                ))
+            }
+        }
+    }
+
+    Ok(ResolvedGuild {
+        log_channel,
+        output_policy: document.output_policy.clone(),
+        render_style: document.render_style.clone(),
+        hide_warnings: document.hide_warnings,
+        prefix: document.prefix.clone(),
+        locale: document.locale.clone(),
+        access,
+        permission: document.permission.clone().into_iter().collect(),
+        output_limit: document.output_limit.clone().into_iter().collect(),
+    })
+}
+
+fn resolve_scope(ctx: &Context, scope: Scope, setting: ScopeSetting) -> Result<ResolvedScope> {
+    let mut remap = Vec::new();
+    for (language_name, compiler_name) in setting.remap {
+        let language_name = LanguageName::from_string(language_name);
+        let language = match ctx.table.find_language(&language_name) {
+            Some(l) => l,
+            None => return Err(Error::UnknownLanguageName(language_name)),
+        };
+
+        let compiler_name = CompilerName::from_string(compiler_name);
+        let compiler = match ctx.table.find_compiler(&compiler_name) {
+            Some(c) => c,
+            None => return Err(Error::UnknownCompilerName(compiler_name)),
+        };
+
+        if compiler.language_id() != language.id() {
+            return Err(Error::RemapMismatch(
+                compiler.name().clone(),
+                language.name().clone(),
+            ));
+        }
+
+        remap.push((language, compiler));
+    }
+
+    let mut options = Vec::new();
+    for (compiler_name, compiler_options) in setting.options {
+        let compiler_name = CompilerName::from_string(compiler_name);
+        let compiler = match ctx.table.find_compiler(&compiler_name) {
+            Some(c) => c,
+            None => return Err(Error::UnknownCompilerName(compiler_name)),
+        };
+
+        options.push((compiler, compiler_options.into_iter().collect()));
+    }
+
+    let mut quota = Vec::new();
+    for (key, limit) in setting.quota {
+        match key.parse() {
+            Ok(key) => quota.push((key, limit)),
+            Err(()) => return Err(Error::InvalidDocumentValue("quota".to_string(), key)),
+        }
+    }
+
+    Ok(ResolvedScope {
+        scope,
+        auto: setting.auto,
+        auto_save: setting.auto_save,
+        remap,
+        options,
+        quota,
+    })
+}
+
+fn diff_guild(ctx: &Context, resolved: &ResolvedGuild, changes: &mut Vec<Change>) -> Result<()> {
+    let mut push = |key: String, old: Option<String>, new: String| {
+        if old.as_ref() != Some(&new) {
+            changes.push(Change {
+                channel_id: None,
+                key,
+                old,
+                new,
+            });
+        }
+    };
+
+    if let Some(channel_id) = resolved.log_channel {
+        let old = ctx.setting.get_log_channel(ctx.guild_id)?;
+        push(
+            "log-channel".to_string(),
+            old.map(|id| id.as_u64().to_string()),
+            channel_id.as_u64().to_string(),
+        );
+    }
+
+    if let Some(policy) = &resolved.output_policy {
+        let old = ctx.setting.get_output_policy(ctx.guild_id)?;
+        push("output-policy".to_string(), old, policy.clone());
+    }
+
+    if let Some(style) = &resolved.render_style {
+        let old = ctx.setting.get_render_style(ctx.guild_id)?;
+        push("render-style".to_string(), old, style.clone());
+    }
+
+    if let Some(hide_warnings) = resolved.hide_warnings {
+        let old = ctx.setting.get_exact_hide_warnings(ctx.guild_id)?;
+        push(
+            "hide-warnings".to_string(),
+            old.map(|x| x.to_string()),
+            hide_warnings.to_string(),
+        );
+    }
+
+    if let Some(prefix) = &resolved.prefix {
+        let old = ctx.setting.get_prefix(ctx.guild_id)?;
+        push("prefix".to_string(), old, prefix.clone());
+    }
+
+    if let Some(locale) = &resolved.locale {
+        let old = ctx.setting.get_locale(ctx.guild_id)?;
+        push("locale".to_string(), old, locale.clone());
+    }
+
+    // entries are only added, so an existing one is shown as unchanged
+    let current_access = list_access(ctx)?;
+    for entry in &resolved.access {
+        let key = entry.to_key();
+        let old = if current_access.contains(entry) {
+            Some(key.clone())
+        } else {
+            None
+        };
+        push("access".to_string(), old, key);
+    }
+
+    let current_permission: HashMap<_, _> = ctx
+        .setting
+        .get_permission_all(ctx.guild_id)?
+        .into_iter()
+        .collect();
+    for (command, permission) in &resolved.permission {
+        push(
+            format!("permission.{}", command),
+            current_permission.get(command).cloned(),
+            permission.clone(),
+        );
+    }
+
+    let current_output_limit: HashMap<_, _> = ctx
+        .setting
+        .get_output_limit_all(ctx.guild_id)?
+        .into_iter()
+        .collect();
+    for (name, limit) in &resolved.output_limit {
+        push(
+            format!("output-limit.{}", name),
+            current_output_limit.get(name).map(|x| x.to_string()),
+            limit.to_string(),
+        );
+    }
+
+    Ok(())
+}
+
+fn diff_scope(ctx: &Context, resolved: &ResolvedScope, changes: &mut Vec<Change>) -> Result<()> {
+    let channel_id = match resolved.scope {
+        Scope::Channel(channel_id) => Some(channel_id),
+        _ => None,
+    };
+    let mut push = |key: String, old: Option<String>, new: String| {
+        if old.as_ref() != Some(&new) {
+            changes.push(Change {
+                channel_id,
+                key,
+                old,
+                new,
+            });
+        }
+    };
+
+    if let Some(auto) = resolved.auto {
+        let old = ctx.setting.get_exact_auto(ctx.guild_id, resolved.scope)?;
+        push(
+            "auto".to_string(),
+            old.map(|x| x.to_string()),
+            auto.to_string(),
+        );
+    }
+
+    if let Some(auto_save) = resolved.auto_save {
+        let old = ctx
+            .setting
+            .get_exact_auto_save(ctx.guild_id, resolved.scope)?;
+        push(
+            "auto-save".to_string(),
+            old.map(|x| x.to_string()),
+            auto_save.to_string(),
+        );
+    }
+
+    let current_remap: HashMap<_, _> = ctx
+        .setting
+        .get_exact_remap_all(ctx.guild_id, resolved.scope)?
+        .into_iter()
+        .collect();
+    for (language, compiler) in &resolved.remap {
+        let old = current_remap
+            .get(&language.id())
+            .map(|&c| ctx.table.get_compiler(c).name().to_string());
+        push(
+            format!("remap.{}", language.name()),
+            old,
+            compiler.name().to_string(),
+        );
+    }
+
+    let current_quota: HashMap<_, _> = ctx
+        .setting
+        .get_quota_all(ctx.guild_id, resolved.scope)?
+        .into_iter()
+        .collect();
+    for (key, limit) in &resolved.quota {
+        push(
+            format!("quota.{}", key),
+            current_quota.get(key.name()).map(|x| x.to_string()),
+            limit.to_string(),
+        );
+    }
+
+    let current_options: HashMap<_, _> = ctx
+        .setting
+        .get_exact_options_all(ctx.guild_id, resolved.scope)?
+        .into_iter()
+        .collect();
+    for (compiler, options) in &resolved.options {
+        let old = current_options
+            .get(&compiler.id())
+            .map(|o| CompilerOptions::from_raw(o).to_raw().replace('\n', " "));
+        push(
+            format!("options.{}", compiler.name()),
+            old,
+            options.to_raw().replace('\n', " "),
+        );
+    }
+
+    Ok(())
+}
+
+fn apply_guild(ctx: &Context, resolved: &ResolvedGuild) -> Result<()> {
+    if let Some(channel_id) = resolved.log_channel {
+        ctx.setting
+            .set_log_channel(ctx.guild_id, Some(channel_id))?;
+    }
+
+    if let Some(policy) = &resolved.output_policy {
+        ctx.setting.set_output_policy(ctx.guild_id, Some(policy))?;
+    }
+
+    if let Some(style) = &resolved.render_style {
+        ctx.setting.set_render_style(ctx.guild_id, Some(style))?;
+    }
+
+    if let Some(hide_warnings) = resolved.hide_warnings {
+        ctx.setting.set_hide_warnings(ctx.guild_id, hide_warnings)?;
+    }
+
+    if let Some(prefix) = &resolved.prefix {
+        ctx.setting.set_prefix(ctx.guild_id, Some(prefix))?;
+    }
+
+    if let Some(locale) = &resolved.locale {
+        ctx.setting
+            .set_locale(ctx.guild_id, Scope::Guild, Some(locale))?;
+    }
+
+    for &entry in &resolved.access {
+        set_access(ctx, entry, true)?;
+    }
+
+    for (command, permission) in &resolved.permission {
+        ctx.setting
+            .set_permission(ctx.guild_id, command, Some(permission))?;
+    }
+
+    for (name, limit) in &resolved.output_limit {
+        ctx.setting
+            .set_output_limit(ctx.guild_id, name, Some(*limit))?;
+    }
+
+    Ok(())
+}
+
+fn apply_scope(ctx: &Context, resolved: &ResolvedScope) -> Result<()> {
+    if let Some(auto) = resolved.auto {
+        ctx.setting
+            .set_exact_auto(ctx.guild_id, resolved.scope, auto)?;
+    }
+
+    if let Some(auto_save) = resolved.auto_save {
+        ctx.setting
+            .set_exact_auto_save(ctx.guild_id, resolved.scope, auto_save)?;
+    }
+
+    for (language, compiler) in &resolved.remap {
+        ctx.setting
+            .set_exact_remap(ctx.guild_id, resolved.scope, language.id(), compiler.id())?;
+    }
+
+    for (compiler, options) in &resolved.options {
+        ctx.setting.set_exact_options(
+            ctx.guild_id,
+            resolved.scope,
+            compiler.id(),
+            &options.to_raw(),
+        )?;
+    }
+
+    for (key, limit) in &resolved.quota {
+        ctx.setting
+            .set_quota(ctx.guild_id, resolved.scope, key.name(), Some(*limit))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::action::export_setting;
+    use crate::model::guild_id::GuildID;
+    use crate::testing::Namespace;
+
+    const DOCUMENT: &str = r#"
+version = 1
+log-channel = "42"
+output-policy = "strict"
+render-style = "embed"
+hide-warnings = true
+prefix = "!rb"
+locale = "en"
+access = ["run:allow:role:7"]
+
+[permission]
+remap = "manage-guild"
+
+[output-limit]
+lines = 20
+
+[guild]
+auto = true
+auto-save = false
+
+[guild.remap]
+cpp = "clang-head"
+
+[guild.options]
+clang-head = ["-O2"]
+
+[guild.quota]
+user-executions = 10
+
+[channels.3]
+auto = false
+
+[channels.3.quota]
+guild-bytes = 4096
+"#;
+
+    #[test]
+    #[ignore = "requires a Redis server at RUNBOT_TEST_REDIS_URI"]
+    fn round_trip() {
+        let namespace = Namespace::new();
+        let ctx = namespace.context(Some(GuildID::from_u64(1)), 2, 3);
+        let document: SettingDocument = toml::from_str(DOCUMENT).unwrap();
+
+        let output = import_setting(&ctx, document.clone(), false).unwrap();
+        assert!(output.applied);
+        assert!(!output.changes.is_empty());
+        assert_eq!(export_setting(&ctx).unwrap(), document);
+
+        // importing the same document again changes nothing
+        let output = import_setting(&ctx, document, false).unwrap();
+        assert!(output.changes.is_empty());
+    }
+
+    #[test]
+    #[ignore = "requires a Redis server at RUNBOT_TEST_REDIS_URI"]
+    fn dry_run_writes_nothing() {
+        let namespace = Namespace::new();
+        let ctx = namespace.context(Some(GuildID::from_u64(1)), 2, 3);
+        let document: SettingDocument = toml::from_str(DOCUMENT).unwrap();
+
+        let output = import_setting(&ctx, document, true).unwrap();
+        assert!(!output.applied);
+        assert!(!output.changes.is_empty());
+        assert!(namespace.keys().is_empty());
+    }
+}
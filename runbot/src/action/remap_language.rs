@@ -1,11 +1,11 @@
 use crate::model::compiler::CompilerName;
 use crate::model::language::LanguageName;
-use crate::setting::Scope;
+use crate::model::scope::Scope;
 use crate::{Context, Error, Result};
 
 pub fn remap_language(
     ctx: &Context,
-    is_global: bool,
+    scope: Scope,
     language_name: LanguageName,
     compiler_name: CompilerName,
 ) -> Result<()> {
@@ -26,11 +26,7 @@ pub fn remap_language(
         ));
     }
 
-    let scope = if is_global {
-        Scope::Guild
-    } else {
-        Scope::Channel(ctx.channel_id)
-    };
+    let scope = ctx.setting_scope(scope);
 
     ctx.setting
         .set_remap(ctx.guild_id, scope, language.id(), compiler.id())?;
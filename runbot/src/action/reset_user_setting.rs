@@ -0,0 +1,5 @@
+use crate::{Context, Result};
+
+pub fn reset_user_setting(ctx: &Context) -> Result<()> {
+    ctx.setting.clear_user(ctx.guild_id, ctx.personal_scope())
+}
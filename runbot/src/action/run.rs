@@ -1,17 +1,13 @@
+use std::time::Instant;
+
 use crate::model::code::Code;
+use crate::model::compiler::Compiler;
 use crate::model::compiler_options::CompilerOptions;
 use crate::model::compiler_spec::CompilerSpec;
-use crate::{Context, Error, Result};
-
-use itertools::Itertools;
-
-pub struct Output {
-    pub status: Option<u32>,
-    pub signal: Option<String>,
-    pub compiler_message: Option<String>,
-    pub program_message: Option<String>,
-    pub url: Option<String>,
-}
+use crate::model::language::LanguageName;
+use crate::model::outcome::{Outcome, Status};
+use crate::model::streams::Streams;
+use crate::{Context, Result};
 
 // Notice that both `compiler_spec` and `code` can specify the compiler to use.
 pub fn run(
@@ -21,32 +17,70 @@ pub fn run(
     options: Option<CompilerOptions>,
     stdin: Option<String>,
     save: bool,
-) -> Result<Output> {
-    let compiler = if let Some(spec) = compiler_spec {
-        ctx.resolve_compiler_spec(&spec)?
-    } else if let Some(lang) = code.language() {
-        ctx.resolve_language_name(lang)?
+) -> Result<Outcome> {
+    let (compiler, inferred_language) = if let Some(spec) = compiler_spec {
+        (ctx.resolve_compiler_spec(&spec)?, None)
     } else {
-        return Err(Error::NoCompilerSpecified);
+        let (language, inferred) = ctx.infer_language(&code)?;
+        let inferred_language = if inferred {
+            Some(language.name().clone())
+        } else {
+            None
+        };
+        (ctx.resolve_language(language)?, inferred_language)
     };
 
+    let options = match options {
+        Some(options) => Some(options),
+        None => ctx.resolve_options(compiler)?,
+    };
+
+    execute(ctx, compiler, inferred_language, code, options, stdin, save)
+}
+
+pub(crate) fn execute(
+    ctx: &Context,
+    compiler: &Compiler,
+    inferred_language: Option<LanguageName>,
+    code: Code,
+    options: Option<CompilerOptions>,
+    stdin: Option<String>,
+    save: bool,
+) -> Result<Outcome> {
     let req = wandbox::api::compile::Request {
         compiler: compiler.wandbox_name().clone(),
         code: code.text().clone(),
-        codes: Vec::new(),
+        codes: code
+            .files()
+            .iter()
+            .map(|f| wandbox::api::compile::Code {
+                file: f.name().clone(),
+                code: f.text().clone(),
+            })
+            .collect(),
         options: None,
         stdin,
-        compiler_option_raw: options.map(|o| o.into_iter().join("\n")),
+        compiler_option_raw: options.map(|o| o.to_raw()),
         runtime_option_raw: None,
         save,
     };
 
-    let res = ctx.wandbox.compile(&req)?;
-    Ok(Output {
-        status: res.status.map(|o| o.parse().unwrap()),
-        signal: res.signal,
-        compiler_message: res.compiler_message,
-        program_message: res.program_message,
+    let started = Instant::now();
+    let res = ctx.compile(&req)?;
+    let elapsed = started.elapsed();
+
+    let streams = Streams {
+        compiler_output: res.compiler_output,
+        compiler_error: res.compiler_error,
+        program_output: res.program_output,
+        program_error: res.program_error,
+    };
+    Ok(Outcome {
+        status: Status::classify(res.status, res.signal, &streams),
+        compiler: compiler.clone(),
+        inferred_language,
+        streams,
         url: res.url,
+        elapsed,
     })
 }
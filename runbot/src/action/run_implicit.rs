@@ -1,47 +1,24 @@
 use crate::model::code::Code;
-use crate::{Context, Error, Result};
+use crate::model::outcome::Outcome;
+use crate::{Context, Result};
 
-pub enum Output {
-    NoRun,
-    Run {
-        status: Option<u32>,
-        signal: Option<String>,
-        compiler_message: Option<String>,
-        program_message: Option<String>,
-        url: Option<String>,
-    },
-}
-
-pub fn run_implicit(ctx: &Context, code: Code, stdin: Option<String>) -> Result<Output> {
+// `None` if the code is not run automatically in the channel
+pub fn run_implicit(ctx: &Context, code: Code, stdin: Option<String>) -> Result<Option<Outcome>> {
     if !ctx.is_auto()? {
-        return Ok(Output::NoRun);
+        return Ok(None);
     }
 
     let save = ctx.is_auto_save()?;
 
-    let compiler = if let Some(lang) = code.language() {
-        ctx.resolve_language_name(lang)?
+    let (language, inferred) = ctx.infer_language(&code)?;
+    let compiler = ctx.resolve_language(language)?;
+    let inferred_language = if inferred {
+        Some(language.name().clone())
     } else {
-        return Err(Error::NoCompilerSpecified);
+        None
     };
 
-    let req = wandbox::api::compile::Request {
-        compiler: compiler.wandbox_name().clone(),
-        code: code.text().clone(),
-        codes: Vec::new(),
-        options: None,
-        stdin,
-        compiler_option_raw: None,
-        runtime_option_raw: None,
-        save,
-    };
+    let options = ctx.resolve_options(compiler)?;
 
-    let res = ctx.wandbox.compile(&req)?;
-    Ok(Output::Run {
-        status: res.status.map(|o| o.parse().unwrap()),
-        signal: res.signal,
-        compiler_message: res.compiler_message,
-        program_message: res.program_message,
-        url: res.url,
-    })
+    super::run::execute(ctx, compiler, inferred_language, code, options, stdin, save).map(Some)
 }
@@ -1,14 +1,15 @@
-use crate::setting::Scope;
+use crate::model::scope::Scope;
 use crate::{Context, Result};
 
-pub fn set_auto(ctx: &Context, is_global: bool, set: bool) -> Result<()> {
-    let scope = if is_global {
-        Scope::Guild
-    } else {
-        Scope::Channel(ctx.channel_id)
-    };
+pub fn set_auto(ctx: &Context, scope: Scope, set: bool) -> Result<()> {
+    let scope = ctx.setting_scope(scope);
 
     ctx.setting.set_auto(ctx.guild_id, scope, set)?;
 
     Ok(())
 }
+
+// whether code is run automatically for the user in the channel
+pub fn is_auto(ctx: &Context) -> Result<bool> {
+    ctx.is_auto()
+}
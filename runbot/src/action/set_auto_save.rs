@@ -1,12 +1,8 @@
-use crate::setting::Scope;
+use crate::model::scope::Scope;
 use crate::{Context, Result};
 
-pub fn set_auto_save(ctx: &Context, is_global: bool, set: bool) -> Result<()> {
-    let scope = if is_global {
-        Scope::Guild
-    } else {
-        Scope::Channel(ctx.channel_id)
-    };
+pub fn set_auto_save(ctx: &Context, scope: Scope, set: bool) -> Result<()> {
+    let scope = ctx.setting_scope(scope);
 
     ctx.setting.set_auto_save(ctx.guild_id, scope, set)?;
 
@@ -0,0 +1,21 @@
+use crate::model::scope::Scope;
+use crate::{Context, Result};
+
+// the locale of messages for the guild, or for the user with `Scope::User`;
+// `None` resets to the default
+pub fn set_locale(ctx: &Context, scope: Scope, locale: Option<&str>) -> Result<()> {
+    let scope = match scope {
+        Scope::User => Scope::User,
+        Scope::Channel | Scope::Guild => Scope::Guild,
+    };
+    ctx.setting
+        .set_locale(ctx.guild_id, ctx.setting_scope(scope), locale)
+}
+
+// the user's locale takes precedence over the guild's
+pub fn get_locale(ctx: &Context) -> Result<Option<String>> {
+    match ctx.setting.get_user_locale(ctx.personal_scope())? {
+        Some(locale) => Ok(Some(locale)),
+        None => ctx.setting.get_locale(ctx.guild_id),
+    }
+}
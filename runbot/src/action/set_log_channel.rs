@@ -0,0 +1,10 @@
+use crate::model::channel_id::ChannelID;
+use crate::{Context, Result};
+
+pub fn set_log_channel(ctx: &Context, channel_id: Option<ChannelID>) -> Result<()> {
+    ctx.setting.set_log_channel(ctx.guild_id, channel_id)
+}
+
+pub fn get_log_channel(ctx: &Context) -> Result<Option<ChannelID>> {
+    ctx.setting.get_log_channel(ctx.guild_id)
+}
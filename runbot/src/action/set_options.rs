@@ -0,0 +1,23 @@
+use crate::model::compiler::CompilerName;
+use crate::model::compiler_options::CompilerOptions;
+use crate::model::scope::Scope;
+use crate::{Context, Error, Result};
+
+pub fn set_options(
+    ctx: &Context,
+    scope: Scope,
+    compiler_name: CompilerName,
+    options: CompilerOptions,
+) -> Result<()> {
+    let compiler = match ctx.table.find_compiler(&compiler_name) {
+        Some(c) => c,
+        None => return Err(Error::UnknownCompilerName(compiler_name)),
+    };
+
+    let scope = ctx.setting_scope(scope);
+
+    ctx.setting
+        .set_options(ctx.guild_id, scope, compiler.id(), &options.to_raw())?;
+
+    Ok(())
+}
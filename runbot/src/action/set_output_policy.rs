@@ -0,0 +1,37 @@
+use crate::{Context, Result};
+
+// `policy` is an opaque string interpreted by the frontend; `None` resets to the default
+pub fn set_output_policy(ctx: &Context, policy: Option<&str>) -> Result<()> {
+    ctx.setting.set_output_policy(ctx.guild_id, policy)
+}
+
+pub fn get_output_policy(ctx: &Context) -> Result<Option<String>> {
+    ctx.setting.get_output_policy(ctx.guild_id)
+}
+
+// `None` resets the limit named `name` to the default
+pub fn set_output_limit(ctx: &Context, name: &str, limit: Option<u64>) -> Result<()> {
+    ctx.setting.set_output_limit(ctx.guild_id, name, limit)
+}
+
+pub fn get_output_limits(ctx: &Context) -> Result<Vec<(String, u64)>> {
+    ctx.setting.get_output_limit_all(ctx.guild_id)
+}
+
+// `style` is an opaque string interpreted by the frontend; `None` resets to the default
+pub fn set_render_style(ctx: &Context, style: Option<&str>) -> Result<()> {
+    ctx.setting.set_render_style(ctx.guild_id, style)
+}
+
+pub fn get_render_style(ctx: &Context) -> Result<Option<String>> {
+    ctx.setting.get_render_style(ctx.guild_id)
+}
+
+// compiler messages are hidden from results when the program could be run
+pub fn set_hide_warnings(ctx: &Context, hide: bool) -> Result<()> {
+    ctx.setting.set_hide_warnings(ctx.guild_id, hide)
+}
+
+pub fn get_hide_warnings(ctx: &Context) -> Result<bool> {
+    ctx.setting.get_hide_warnings(ctx.guild_id)
+}
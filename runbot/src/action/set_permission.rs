@@ -0,0 +1,17 @@
+use crate::{Context, Result};
+
+// `permission` is an opaque string interpreted by the frontend; `None` resets to the default
+pub fn set_permission(ctx: &Context, command: &str, permission: Option<&str>) -> Result<()> {
+    ctx.setting
+        .set_permission(ctx.guild_id, command, permission)
+}
+
+pub fn get_permission(ctx: &Context, command: &str) -> Result<Option<String>> {
+    ctx.setting.get_permission(ctx.guild_id, command)
+}
+
+pub fn list_permissions(ctx: &Context) -> Result<Vec<(String, String)>> {
+    let mut permissions = ctx.setting.get_permission_all(ctx.guild_id)?;
+    permissions.sort();
+    Ok(permissions)
+}
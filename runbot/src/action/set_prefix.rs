@@ -0,0 +1,10 @@
+use crate::{Context, Result};
+
+// the command prefix of the guild; `None` resets to the default
+pub fn set_prefix(ctx: &Context, prefix: Option<&str>) -> Result<()> {
+    ctx.setting.set_prefix(ctx.guild_id, prefix)
+}
+
+pub fn get_prefix(ctx: &Context) -> Result<Option<String>> {
+    ctx.setting.get_prefix(ctx.guild_id)
+}
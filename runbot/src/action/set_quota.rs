@@ -0,0 +1,11 @@
+use crate::model::quota::QuotaKey;
+use crate::model::scope::Scope;
+use crate::{Context, Result};
+
+// `None` removes the quota; a channel quota takes precedence over the guild one
+pub fn set_quota(ctx: &Context, scope: Scope, key: QuotaKey, limit: Option<u64>) -> Result<()> {
+    let scope = ctx.setting_scope(scope);
+
+    ctx.setting
+        .set_quota(ctx.guild_id, scope, key.name(), limit)
+}
@@ -0,0 +1,31 @@
+use crate::model::quota::{QuotaKey, QuotaSubject, Usage};
+use crate::{Context, Result};
+
+pub struct Output {
+    pub guild: Usage,
+    pub user: Usage,
+    pub quotas: Vec<(QuotaKey, Option<u64>)>,
+}
+
+impl Output {
+    pub fn usage_of(&self, key: QuotaKey) -> u64 {
+        match key.subject {
+            QuotaSubject::Guild => self.guild.get(key.metric),
+            QuotaSubject::User => self.user.get(key.metric),
+        }
+    }
+}
+
+// today's consumption, along with the quotas effective in the channel
+pub fn usage(ctx: &Context) -> Result<Output> {
+    let quotas = QuotaKey::ALL
+        .iter()
+        .map(|&key| Ok((key, ctx.resolve_quota(key)?)))
+        .collect::<Result<_>>()?;
+
+    Ok(Output {
+        guild: ctx.guild_usage()?,
+        user: ctx.user_usage()?,
+        quotas,
+    })
+}
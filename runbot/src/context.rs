@@ -1,11 +1,17 @@
 use std::sync::Arc;
 
+use crate::limiter::Limiter;
 use crate::model::channel_id::ChannelID;
+use crate::model::code::Code;
 use crate::model::compiler::{Compiler, CompilerName};
+use crate::model::compiler_options::CompilerOptions;
 use crate::model::compiler_spec::CompilerSpec;
 use crate::model::guild_id::GuildID;
 use crate::model::language::{Language, LanguageName};
-use crate::setting::Setting;
+use crate::model::quota::{QuotaKey, QuotaSubject, Usage};
+use crate::model::scope::Scope;
+use crate::model::user_id::UserID;
+use crate::setting::{self, Setting, SettingCache};
 use crate::table::Table;
 use crate::{Error, Result};
 
@@ -15,29 +21,134 @@ pub struct Context {
     pub(crate) setting: Setting,
     pub(crate) table: Table,
     pub(crate) wandbox: wandbox::blocking::Client,
+    pub(crate) limiter: Limiter,
     pub(crate) guild_id: GuildID,
     pub(crate) channel_id: ChannelID,
+    pub(crate) user_id: UserID,
 }
 
 impl Context {
+    #[allow(clippy::too_many_arguments)]
+    // `guild_id` is `None` in direct messages
     pub fn new(
-        guild_id: GuildID,
+        guild_id: Option<GuildID>,
         channel_id: ChannelID,
+        user_id: UserID,
         wandbox_client: wandbox::blocking::Client,
         redis_connection: Arc<Mutex<redis::Connection>>,
         redis_prefix: String,
+        setting_cache: SettingCache,
+        limiter: Limiter,
         table: Table,
     ) -> Context {
-        let setting = Setting::new(redis_connection, redis_prefix);
+        let setting = Setting::new(redis_connection, redis_prefix, setting_cache, user_id);
         Context {
             setting,
             table,
             wandbox: wandbox_client,
-            guild_id,
+            limiter,
+            guild_id: guild_id.unwrap_or(GuildID::DIRECT),
             channel_id,
+            user_id,
         }
     }
 
+    pub(crate) fn is_direct(&self) -> bool {
+        self.guild_id == GuildID::DIRECT
+    }
+
+    // The user's own settings. Those made in direct messages do not apply in guilds,
+    // and vice versa.
+    pub(crate) fn personal_scope(&self) -> setting::Scope {
+        if self.is_direct() {
+            setting::Scope::Direct(self.user_id)
+        } else {
+            setting::Scope::User(self.user_id)
+        }
+    }
+
+    // In direct messages, every setting belongs to the user
+    pub(crate) fn setting_scope(&self, scope: Scope) -> setting::Scope {
+        if self.is_direct() {
+            return self.personal_scope();
+        }
+        match scope {
+            Scope::Channel => setting::Scope::Channel(self.channel_id),
+            Scope::Guild => setting::Scope::Guild,
+            Scope::User => self.personal_scope(),
+        }
+    }
+
+    // All requests to Wandbox go through here to be rate limited
+    pub(crate) fn compile(
+        &self,
+        request: &wandbox::api::compile::Request,
+    ) -> Result<wandbox::api::compile::Response> {
+        let bytes = request.code.len() + request.codes.iter().map(|c| c.code.len()).sum::<usize>();
+        let bytes = bytes as u64;
+        // quotas are for guilds, so direct messages are only rate limited
+        if self.is_direct() {
+            let _permit = self.limiter.acquire_direct(self.user_id)?;
+            return Ok(self.wandbox.compile(request)?);
+        }
+        let _permit = self
+            .limiter
+            .acquire(self.guild_id, self.channel_id, self.user_id)?;
+        self.count_usage(bytes)?;
+        match self.wandbox.compile(request) {
+            Ok(response) => Ok(response),
+            Err(e) => {
+                // requests which never ran are not counted
+                self.setting
+                    .remove_usage(self.guild_id, self.user_id, bytes)?;
+                Err(e.into())
+            }
+        }
+    }
+
+    // channels can have their own quota to give heavier allowances
+    pub(crate) fn resolve_quota(&self, key: QuotaKey) -> Result<Option<u64>> {
+        let channel = setting::Scope::Channel(self.channel_id);
+        if let Some(limit) = self.setting.get_quota(self.guild_id, channel, key.name())? {
+            return Ok(Some(limit));
+        }
+        self.setting
+            .get_quota(self.guild_id, setting::Scope::Guild, key.name())
+    }
+
+    pub(crate) fn guild_usage(&self) -> Result<Usage> {
+        self.setting.get_usage(self.guild_id, None)
+    }
+
+    pub(crate) fn user_usage(&self) -> Result<Usage> {
+        self.setting.get_usage(self.guild_id, Some(self.user_id))
+    }
+
+    // Counts the execution first and checks the quotas with the result, so that concurrent
+    // executions cannot exceed them together. An execution exceeding any is taken back.
+    fn count_usage(&self, bytes: u64) -> Result<()> {
+        let mut limits = Vec::new();
+        for &key in &QuotaKey::ALL {
+            if let Some(limit) = self.resolve_quota(key)? {
+                limits.push((key, limit));
+            }
+        }
+
+        let (guild, user) = self.setting.add_usage(self.guild_id, self.user_id, bytes)?;
+        for (key, limit) in limits {
+            let usage = match key.subject {
+                QuotaSubject::Guild => guild,
+                QuotaSubject::User => user,
+            };
+            if usage.get(key.metric) > limit {
+                self.setting
+                    .remove_usage(self.guild_id, self.user_id, bytes)?;
+                return Err(Error::QuotaExceeded(key));
+            }
+        }
+        Ok(())
+    }
+
     pub(crate) fn resolve_compiler_spec(&self, spec: &CompilerSpec) -> Result<&Compiler> {
         if let Some(language) = self.table.find_language(spec.as_language_name()) {
             self.resolve_language(language)
@@ -48,31 +159,77 @@ impl Context {
         }
     }
 
-    pub(crate) fn resolve_language_name(&self, language_name: &LanguageName) -> Result<&Compiler> {
-        if let Some(language) = self.table.find_language(language_name) {
-            self.resolve_language(language)
-        } else {
-            Err(Error::UnknownLanguageName(language_name.clone()))
+    // The language of `code`, and whether it is inferred rather than named by the tag.
+    // An unknown tag may be a file extension, and untagged code is guessed from its text.
+    pub(crate) fn infer_language(&self, code: &Code) -> Result<(&Language, bool)> {
+        match code.language() {
+            Some(name) => {
+                if let Some(language) = self.table.find_language(name) {
+                    return Ok((language, false));
+                }
+                self.table
+                    .find_language_by_extension(name)
+                    .map(|l| (l, true))
+                    .ok_or_else(|| Error::UnknownLanguageName(name.clone()))
+            }
+            None => self
+                .table
+                .guess_language(code.text())
+                .map(|l| (l, true))
+                .ok_or(Error::NoCompilerSpecified),
         }
     }
 
     pub(crate) fn resolve_language(&self, language: &Language) -> Result<&Compiler> {
-        if let Some(compiler_id) = self
+        let remap = match self
             .setting
-            .get_remap(self.guild_id, self.channel_id, language.id())?
-            .or_else(|| language.default_compiler_id())
+            .get_user_remap(self.personal_scope(), language.id())?
         {
+            Some(compiler_id) => Some(compiler_id),
+            None => self
+                .setting
+                .get_remap(self.guild_id, self.channel_id, language.id())?,
+        };
+
+        if let Some(compiler_id) = remap.or_else(|| language.default_compiler_id()) {
             Ok(self.table.get_compiler(compiler_id))
         } else {
             Err(Error::UnmappedLanguage(language.name().clone()))
         }
     }
 
+    pub(crate) fn resolve_options(&self, compiler: &Compiler) -> Result<Option<CompilerOptions>> {
+        let options = match self
+            .setting
+            .get_user_options(self.personal_scope(), compiler.id())?
+        {
+            Some(options) => Some(options),
+            None => self
+                .setting
+                .get_options(self.guild_id, self.channel_id, compiler.id())?,
+        };
+        Ok(options.map(|o| CompilerOptions::from_raw(&o)))
+    }
+
     pub(crate) fn is_auto(&self) -> Result<bool> {
-        self.setting.get_auto(self.guild_id, self.channel_id)
+        match self.setting.get_user_auto(self.personal_scope())? {
+            Some(auto) => Ok(auto),
+            None => self.is_channel_auto(),
+        }
     }
 
     pub(crate) fn is_auto_save(&self) -> Result<bool> {
+        match self.setting.get_user_auto_save(self.personal_scope())? {
+            Some(auto_save) => Ok(auto_save),
+            None => self.is_channel_auto_save(),
+        }
+    }
+
+    pub(crate) fn is_channel_auto(&self) -> Result<bool> {
+        self.setting.get_auto(self.guild_id, self.channel_id)
+    }
+
+    pub(crate) fn is_channel_auto_save(&self) -> Result<bool> {
         self.setting.get_auto_save(self.guild_id, self.channel_id)
     }
 
@@ -90,4 +247,74 @@ impl Context {
             .collect();
         Ok(remaps)
     }
+
+    pub(crate) fn all_options(&self) -> Result<Vec<(&CompilerName, CompilerOptions)>> {
+        let options = self
+            .setting
+            .get_options_all(self.guild_id, self.channel_id)?
+            .into_iter()
+            .map(|(compiler_id, options)| {
+                (
+                    self.table.get_compiler(compiler_id).name(),
+                    CompilerOptions::from_raw(&options),
+                )
+            })
+            .collect();
+        Ok(options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::action::remap_language;
+    use crate::testing::Namespace;
+
+    fn resolve_cpp(ctx: &Context) -> String {
+        let cpp = ctx
+            .table
+            .find_language(&LanguageName::from_string("cpp".to_string()))
+            .unwrap();
+        ctx.resolve_language(cpp).unwrap().name().to_string()
+    }
+
+    #[test]
+    #[ignore = "requires a Redis server at RUNBOT_TEST_REDIS_URI"]
+    fn direct_remap_stays_in_direct_messages() {
+        let ns = Namespace::new();
+        let direct = ns.context(None, 2, 3);
+        let guild = ns.context(Some(GuildID::from_u64(1)), 4, 3);
+
+        remap_language(
+            &direct,
+            Scope::User,
+            LanguageName::from_string("cpp".to_string()),
+            CompilerName::from_string("clang-head".to_string()),
+        )
+        .unwrap();
+
+        assert_eq!(resolve_cpp(&direct), "clang-head");
+        assert_eq!(resolve_cpp(&guild), "gcc-head");
+    }
+
+    #[test]
+    #[ignore = "requires a Redis server at RUNBOT_TEST_REDIS_URI"]
+    fn exceeding_execution_is_not_counted() {
+        let ns = Namespace::new();
+        let ctx = ns.context(Some(GuildID::from_u64(1)), 2, 3);
+        let key: QuotaKey = "user-executions".parse().unwrap();
+        ctx.setting
+            .set_quota(ctx.guild_id, setting::Scope::Guild, key.name(), Some(1))
+            .unwrap();
+
+        ctx.count_usage(10).unwrap();
+        assert!(matches!(ctx.count_usage(10), Err(Error::QuotaExceeded(k)) if k == key));
+        assert_eq!(
+            ctx.user_usage().unwrap(),
+            Usage {
+                executions: 1,
+                bytes: 10
+            }
+        );
+    }
 }
@@ -1,8 +1,11 @@
 use std::result;
+use std::time::Duration;
 
+use crate::model::access::AccessTarget;
 use crate::model::compiler::CompilerName;
 use crate::model::compiler_spec::CompilerSpec;
 use crate::model::language::LanguageName;
+use crate::model::quota::QuotaKey;
 
 use err_derive::Error;
 
@@ -20,6 +23,22 @@ pub enum Error {
     NoCompilerSpecified,
     #[error(display = "{} is not a compiler for {}", _0, _1)]
     RemapMismatch(CompilerName, LanguageName),
+    #[error(display = "unsupported setting document version {}", _0)]
+    UnsupportedDocumentVersion(u32),
+    #[error(display = "invalid value {} for {}", _1, _0)]
+    InvalidDocumentValue(String, String),
+    #[error(display = "invalid channel ID {}", _0)]
+    InvalidChannelID(String),
+    #[error(display = "stored schema version {} is newer than this runbot", _0)]
+    UnsupportedSchemaVersion(u32),
+    #[error(display = "not allowed to {}", _0)]
+    AccessDenied(AccessTarget),
+    #[error(display = "rate limited (retry after {:?})", _0)]
+    RateLimited(Duration),
+    #[error(display = "too many executions in flight")]
+    TooManyInFlight,
+    #[error(display = "daily quota of {} exceeded", _0)]
+    QuotaExceeded(QuotaKey),
     #[error(display = "network error: {}", _0)]
     Wandbox(#[error(source)] wandbox::Error),
     #[error(display = "database error: {}", _0)]
@@ -3,10 +3,16 @@
 pub mod action;
 mod context;
 mod error;
+mod limiter;
+pub mod migration;
 pub mod model;
 mod setting;
 mod table;
+#[cfg(test)]
+mod testing;
 
 pub use context::Context;
 pub use error::{Error, Result};
+pub use limiter::{Limiter, Limits, ParseRateLimitError, RateLimit};
+pub use setting::SettingCache;
 pub use table::Table;
@@ -0,0 +1,209 @@
+use std::collections::HashMap;
+use std::result;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::model::channel_id::ChannelID;
+use crate::model::guild_id::GuildID;
+use crate::model::user_id::UserID;
+use crate::{Error, Result};
+
+use err_derive::Error;
+use parking_lot::Mutex;
+
+// `capacity` executions at once, refilled over `period`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    capacity: u32,
+    period: Duration,
+}
+
+impl RateLimit {
+    pub fn new(capacity: u32, period: Duration) -> RateLimit {
+        RateLimit { capacity, period }
+    }
+
+    fn refill_rate(&self) -> f64 {
+        f64::from(self.capacity) / self.period.as_secs_f64()
+    }
+}
+
+#[derive(Debug, Clone, Error)]
+#[error(display = "rate limit must be in the form of COUNT/SECONDS")]
+pub struct ParseRateLimitError;
+
+impl FromStr for RateLimit {
+    type Err = ParseRateLimitError;
+    fn from_str(s: &str) -> result::Result<RateLimit, ParseRateLimitError> {
+        let mut parts = s.splitn(2, '/');
+        let capacity = parts.next().and_then(|x| x.trim().parse().ok());
+        let secs = parts.next().and_then(|x| x.trim().parse().ok());
+        match (capacity, secs) {
+            (Some(capacity), Some(secs)) if capacity > 0 && secs > 0 => {
+                Ok(RateLimit::new(capacity, Duration::from_secs(secs)))
+            }
+            _ => Err(ParseRateLimitError),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum BucketKey {
+    User(UserID),
+    Channel(ChannelID),
+    Guild(GuildID),
+    Direct(UserID),
+}
+
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl Bucket {
+    fn full(limit: RateLimit) -> Bucket {
+        Bucket {
+            tokens: f64::from(limit.capacity),
+            updated_at: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self, limit: RateLimit) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.updated_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * limit.refill_rate()).min(f64::from(limit.capacity));
+        self.updated_at = now;
+    }
+
+    fn is_full(&self, limit: RateLimit) -> bool {
+        self.tokens >= f64::from(limit.capacity)
+    }
+
+    // time until one token becomes available
+    fn wait_time(&self, limit: RateLimit) -> Duration {
+        let missing = (1.0 - self.tokens).max(0.0);
+        Duration::from_secs_f64(missing / limit.refill_rate())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Limits {
+    pub user: Option<RateLimit>,
+    pub channel: Option<RateLimit>,
+    pub guild: Option<RateLimit>,
+    // per user in direct messages, in place of the limits above
+    pub direct: Option<RateLimit>,
+    pub max_in_flight: Option<usize>,
+}
+
+struct Inner {
+    buckets: HashMap<BucketKey, Bucket>,
+    in_flight: usize,
+}
+
+// Shared among all contexts, so that limits apply across messages
+#[derive(Clone)]
+pub struct Limiter {
+    limits: Limits,
+    inner: Arc<Mutex<Inner>>,
+}
+
+// Releases the in-flight slot when dropped
+pub(crate) struct Permit {
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        self.inner.lock().in_flight -= 1;
+    }
+}
+
+impl Limiter {
+    pub fn new(limits: Limits) -> Limiter {
+        Limiter {
+            limits,
+            inner: Arc::new(Mutex::new(Inner {
+                buckets: HashMap::new(),
+                in_flight: 0,
+            })),
+        }
+    }
+
+    fn limit_of(&self, key: BucketKey) -> Option<RateLimit> {
+        match key {
+            BucketKey::User(_) => self.limits.user,
+            BucketKey::Channel(_) => self.limits.channel,
+            BucketKey::Guild(_) => self.limits.guild,
+            BucketKey::Direct(_) => self.limits.direct,
+        }
+    }
+
+    // Tokens are taken only when every bucket has one, so that a throttled
+    // message does not count against the other limits.
+    pub(crate) fn acquire(
+        &self,
+        guild_id: GuildID,
+        channel_id: ChannelID,
+        user_id: UserID,
+    ) -> Result<Permit> {
+        self.acquire_keys(&[
+            BucketKey::User(user_id),
+            BucketKey::Channel(channel_id),
+            BucketKey::Guild(guild_id),
+        ])
+    }
+
+    pub(crate) fn acquire_direct(&self, user_id: UserID) -> Result<Permit> {
+        self.acquire_keys(&[BucketKey::Direct(user_id)])
+    }
+
+    fn acquire_keys(&self, keys: &[BucketKey]) -> Result<Permit> {
+        let mut inner = self.inner.lock();
+
+        if let Some(max) = self.limits.max_in_flight {
+            if inner.in_flight >= max {
+                return Err(Error::TooManyInFlight);
+            }
+        }
+
+        let keys: Vec<_> = keys
+            .iter()
+            .filter_map(|&k| self.limit_of(k).map(|l| (k, l)))
+            .collect();
+
+        let mut wait = Duration::from_secs(0);
+        for &(key, limit) in &keys {
+            let bucket = inner
+                .buckets
+                .entry(key)
+                .or_insert_with(|| Bucket::full(limit));
+            bucket.refill(limit);
+            wait = wait.max(bucket.wait_time(limit));
+        }
+        if wait > Duration::from_secs(0) {
+            return Err(Error::RateLimited(wait));
+        }
+
+        for (key, _) in &keys {
+            if let Some(bucket) = inner.buckets.get_mut(key) {
+                bucket.tokens -= 1.0;
+            }
+        }
+
+        // full buckets carry no state, so drop them to keep the map small
+        inner.buckets.retain(|&k, b| match self.limit_of(k) {
+            Some(limit) => {
+                b.refill(limit);
+                !b.is_full(limit)
+            }
+            None => false,
+        });
+
+        inner.in_flight += 1;
+        Ok(Permit {
+            inner: Arc::clone(&self.inner),
+        })
+    }
+}
@@ -0,0 +1,151 @@
+use crate::{Error, Result};
+
+use redis::Commands;
+
+// Each migration upgrades the stored data from `version - 1` to `version`.
+// Migrations must be idempotent so that an interrupted run can be restarted safely.
+struct Migration {
+    version: u32,
+    description: &'static str,
+    run: fn(&mut redis::Connection, &str) -> Result<()>,
+}
+
+const MIGRATIONS: &[Migration] = &[Migration {
+    version: 1,
+    description: "move guild defaults from {prefix}:channel:{guild}:default:{field} to {prefix}:guild:{guild}:{field}",
+    run: move_guild_defaults,
+}];
+
+pub fn schema_version() -> u32 {
+    MIGRATIONS.last().map_or(0, |m| m.version)
+}
+
+fn version_key(prefix: &str) -> String {
+    format!("{}:schema_version", prefix)
+}
+
+pub fn stored_version(conn: &mut redis::Connection, prefix: &str) -> Result<u32> {
+    let version: Option<u32> = conn.get(version_key(prefix))?;
+    Ok(version.unwrap_or(0))
+}
+
+// Runs all pending migrations and returns the descriptions of applied ones.
+pub fn migrate(conn: &mut redis::Connection, prefix: &str) -> Result<Vec<&'static str>> {
+    let current = stored_version(conn, prefix)?;
+    if current > schema_version() {
+        return Err(Error::UnsupportedSchemaVersion(current));
+    }
+
+    let mut applied = Vec::new();
+    for migration in MIGRATIONS.iter().filter(|m| m.version > current) {
+        (migration.run)(conn, prefix)?;
+        conn.set(version_key(prefix), migration.version)?;
+        applied.push(migration.description);
+    }
+
+    Ok(applied)
+}
+
+fn move_guild_defaults(conn: &mut redis::Connection, prefix: &str) -> Result<()> {
+    let channel_prefix = format!("{}:channel:", prefix);
+    let keys: Vec<String> = conn
+        .scan_match::<_, String>(format!("{}*:default:*", channel_prefix))?
+        .collect();
+
+    for key in keys {
+        let rest = match key.strip_prefix(&channel_prefix) {
+            Some(rest) => rest,
+            None => continue,
+        };
+        let (guild_id, field) = match rest.splitn(3, ':').collect::<Vec<_>>()[..] {
+            [guild_id, "default", field] => (guild_id, field),
+            _ => continue,
+        };
+
+        let new_key = format!("{}:guild:{}:{}", prefix, guild_id, field);
+        conn.rename(key, new_key)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::Namespace;
+
+    #[test]
+    #[ignore = "requires a Redis server at RUNBOT_TEST_REDIS_URI"]
+    fn move_guild_defaults_renames_keys() {
+        let namespace = Namespace::new();
+        let prefix = &namespace.prefix;
+        let mut conn = namespace.conn.lock();
+        let _: () = conn
+            .set(format!("{}:channel:1:default:auto", prefix), 1)
+            .unwrap();
+        let _: () = conn
+            .hset(format!("{}:channel:1:default:remap", prefix), 2, 3)
+            .unwrap();
+        let _: () = conn.set(format!("{}:channel:1:4:auto", prefix), 1).unwrap();
+
+        let applied = migrate(&mut conn, prefix).unwrap();
+        assert_eq!(applied.len(), MIGRATIONS.len());
+        drop(conn);
+
+        let keys = namespace.keys();
+        let expected = vec![
+            format!("{}:channel:1:4:auto", prefix),
+            format!("{}:guild:1:auto", prefix),
+            format!("{}:guild:1:remap", prefix),
+            format!("{}:schema_version", prefix),
+        ];
+        assert_eq!(keys, expected);
+
+        let mut conn = namespace.conn.lock();
+        let remap: Option<u64> = conn.hget(format!("{}:guild:1:remap", prefix), 2).unwrap();
+        assert_eq!(remap, Some(3));
+    }
+
+    #[test]
+    #[ignore = "requires a Redis server at RUNBOT_TEST_REDIS_URI"]
+    fn migrate_bumps_schema_version() {
+        let namespace = Namespace::new();
+        let mut conn = namespace.conn.lock();
+        assert_eq!(stored_version(&mut conn, &namespace.prefix).unwrap(), 0);
+
+        migrate(&mut conn, &namespace.prefix).unwrap();
+        assert_eq!(
+            stored_version(&mut conn, &namespace.prefix).unwrap(),
+            schema_version()
+        );
+    }
+
+    #[test]
+    #[ignore = "requires a Redis server at RUNBOT_TEST_REDIS_URI"]
+    fn migrate_twice_does_nothing() {
+        let namespace = Namespace::new();
+        let prefix = &namespace.prefix;
+        let mut conn = namespace.conn.lock();
+        migrate(&mut conn, prefix).unwrap();
+
+        // keys in the old layout written afterwards are left as they are
+        let key = format!("{}:channel:1:default:auto", prefix);
+        let _: () = conn.set(&key, 1).unwrap();
+        assert!(migrate(&mut conn, prefix).unwrap().is_empty());
+        assert!(conn.exists::<_, bool>(&key).unwrap());
+    }
+
+    #[test]
+    #[ignore = "requires a Redis server at RUNBOT_TEST_REDIS_URI"]
+    fn migrate_rejects_newer_version() {
+        let namespace = Namespace::new();
+        let mut conn = namespace.conn.lock();
+        let newer = schema_version() + 1;
+        let _: () = conn.set(version_key(&namespace.prefix), newer).unwrap();
+
+        match migrate(&mut conn, &namespace.prefix) {
+            Err(Error::UnsupportedSchemaVersion(v)) => assert_eq!(v, newer),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
@@ -1,3 +1,5 @@
+pub mod access;
+pub mod audit;
 pub mod channel_id;
 pub mod code;
 pub mod compiler;
@@ -5,3 +7,10 @@ pub mod compiler_options;
 pub mod compiler_spec;
 pub mod guild_id;
 pub mod language;
+pub mod outcome;
+pub mod quota;
+pub mod role_id;
+pub mod scope;
+pub mod setting_document;
+pub mod streams;
+pub mod user_id;
@@ -0,0 +1,115 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::model::role_id::RoleID;
+use crate::model::user_id::UserID;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessTarget {
+    Run,
+    RunSave,
+    Auto,
+}
+
+impl AccessTarget {
+    pub const ALL: [AccessTarget; 3] =
+        [AccessTarget::Run, AccessTarget::RunSave, AccessTarget::Auto];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            AccessTarget::Run => "run",
+            AccessTarget::RunSave => "run-save",
+            AccessTarget::Auto => "auto",
+        }
+    }
+}
+
+impl fmt::Display for AccessTarget {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for AccessTarget {
+    type Err = ();
+    fn from_str(s: &str) -> Result<AccessTarget, ()> {
+        match s {
+            "run" => Ok(AccessTarget::Run),
+            "run-save" => Ok(AccessTarget::RunSave),
+            "auto" => Ok(AccessTarget::Auto),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessRule {
+    Allow,
+    Deny,
+}
+
+impl AccessRule {
+    pub fn name(self) -> &'static str {
+        match self {
+            AccessRule::Allow => "allow",
+            AccessRule::Deny => "deny",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Principal {
+    Role(RoleID),
+    User(UserID),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccessEntry {
+    pub target: AccessTarget,
+    pub rule: AccessRule,
+    pub principal: Principal,
+}
+
+impl AccessEntry {
+    // entries are stored as `{target}:{rule}:{role|user}:{id}`
+    pub(crate) fn to_key(self) -> String {
+        let (kind, id) = match self.principal {
+            Principal::Role(id) => ("role", id.as_u64()),
+            Principal::User(id) => ("user", id.as_u64()),
+        };
+        format!(
+            "{}:{}:{}:{}",
+            self.target.name(),
+            self.rule.name(),
+            kind,
+            id
+        )
+    }
+
+    pub(crate) fn from_key(key: &str) -> Option<AccessEntry> {
+        let parts: Vec<_> = key.split(':').collect();
+        let (target, rule, kind, id) = match parts[..] {
+            [target, rule, kind, id] => (target, rule, kind, id),
+            _ => return None,
+        };
+
+        let target = target.parse().ok()?;
+        let rule = match rule {
+            "allow" => AccessRule::Allow,
+            "deny" => AccessRule::Deny,
+            _ => return None,
+        };
+        let id = id.parse().ok()?;
+        let principal = match kind {
+            "role" => Principal::Role(RoleID::from_u64(id)),
+            "user" => Principal::User(UserID::from_u64(id)),
+            _ => return None,
+        };
+
+        Some(AccessEntry {
+            target,
+            rule,
+            principal,
+        })
+    }
+}
@@ -0,0 +1,23 @@
+use crate::model::channel_id::ChannelID;
+use crate::model::user_id::UserID;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditScope {
+    Channel(ChannelID),
+    Guild,
+}
+
+#[derive(Debug, Clone)]
+pub struct AuditEntry {
+    pub actor: UserID,
+    pub scope: AuditScope,
+    pub key: String,
+    // `None` means that the value is not set
+    pub old: Option<String>,
+    pub new: Option<String>,
+    // seconds since the UNIX epoch
+    pub timestamp: u64,
+}
@@ -1,8 +1,28 @@
 use crate::model::language::LanguageName;
 
+use derive_more::Constructor;
+
+// a file compiled along with the main code, e.g. a header
+#[derive(Debug, Clone, Constructor)]
+pub struct CodeFile {
+    name: String,
+    text: String,
+}
+
+impl CodeFile {
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn text(&self) -> &String {
+        &self.text
+    }
+}
+
 pub struct Code {
     language: Option<LanguageName>,
     text: String,
+    files: Vec<CodeFile>,
 }
 
 impl Code {
@@ -10,6 +30,7 @@ impl Code {
         Code {
             text,
             language: Some(language),
+            files: Vec::new(),
         }
     }
 
@@ -17,9 +38,14 @@ impl Code {
         Code {
             text,
             language: None,
+            files: Vec::new(),
         }
     }
 
+    pub fn add_file(&mut self, file: CodeFile) {
+        self.files.push(file);
+    }
+
     pub fn text(&self) -> &String {
         &self.text
     }
@@ -27,4 +53,8 @@ impl Code {
     pub fn language(&self) -> Option<&LanguageName> {
         self.language.as_ref()
     }
+
+    pub fn files(&self) -> &[CodeFile] {
+        &self.files
+    }
 }
@@ -7,6 +7,19 @@ impl CompilerOptions {
     pub fn new() -> CompilerOptions {
         CompilerOptions::default()
     }
+
+    // options are stored and sent to Wandbox as newline-separated raw string
+    pub fn from_raw(raw: &str) -> CompilerOptions {
+        raw.lines().map(str::to_owned).collect()
+    }
+
+    pub fn to_raw(&self) -> String {
+        self.0.join("\n")
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
 }
 
 impl IntoIterator for CompilerOptions {
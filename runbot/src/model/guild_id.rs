@@ -1,7 +1,10 @@
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct GuildID(u64);
 
 impl GuildID {
+    // stands for direct messages, which belong to no guild; no snowflake is zero
+    pub const DIRECT: GuildID = GuildID(0);
+
     pub fn from_u64(id: u64) -> GuildID {
         GuildID(id)
     }
@@ -77,11 +77,31 @@ impl LanguageName {
     }
 }
 
+// Hints to guess the language of untagged code
+#[derive(Debug, Clone, Default, Constructor)]
+pub struct Heuristics {
+    // interpreter names in a shebang line, without versions (e.g. `python` for `python3`)
+    shebangs: Vec<String>,
+    keywords: Vec<String>,
+}
+
+impl Heuristics {
+    pub fn shebangs(&self) -> &[String] {
+        &self.shebangs
+    }
+
+    pub fn keywords(&self) -> &[String] {
+        &self.keywords
+    }
+}
+
 #[derive(Debug, Clone, Constructor)]
 pub struct Language {
     id: LanguageID,
     name: LanguageName,
     aliases: HashSet<LanguageName>,
+    extensions: HashSet<LanguageName>,
+    heuristics: Heuristics,
     default_compiler_id: Option<CompilerID>,
 }
 
@@ -98,6 +118,14 @@ impl Language {
         &self.aliases
     }
 
+    pub fn extensions(&self) -> &HashSet<LanguageName> {
+        &self.extensions
+    }
+
+    pub fn heuristics(&self) -> &Heuristics {
+        &self.heuristics
+    }
+
     pub fn default_compiler_id(&self) -> Option<CompilerID> {
         self.default_compiler_id
     }
@@ -105,4 +133,21 @@ impl Language {
     pub fn is_named_as(&self, name: &LanguageName) -> bool {
         &self.name == name || self.aliases.contains(name)
     }
+
+    pub fn has_extension(&self, extension: &LanguageName) -> bool {
+        self.extensions.contains(extension)
+    }
+
+    pub fn is_interpreted_by(&self, interpreter: &str) -> bool {
+        self.heuristics.shebangs.iter().any(|s| s == interpreter)
+    }
+
+    // the number of distinct keywords found in `text`
+    pub fn keyword_score(&self, text: &str) -> usize {
+        self.heuristics
+            .keywords
+            .iter()
+            .filter(|k| text.contains(k.as_str()))
+            .count()
+    }
 }
@@ -0,0 +1,165 @@
+use std::time::Duration;
+
+use crate::model::compiler::Compiler;
+use crate::model::language::LanguageName;
+use crate::model::streams::Streams;
+
+// signals Wandbox uses to stop programs exceeding the time limit
+const TIMEOUT_SIGNALS: &[&str] = &["CPU time limit exceeded", "SIGXCPU"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Status {
+    Success,
+    CompileFailure(i32),
+    Failure(i32),
+    Signal(String),
+    Timeout,
+    // the raw status if Wandbox returned one that is not a number
+    Unknown(Option<String>),
+}
+
+// Compilers that succeed may still report warnings, which mention no errors.
+// Messages mentioning neither (e.g. those of Go) are taken as errors.
+fn is_warnings_only(message: &str) -> bool {
+    let message = message.to_lowercase();
+    message.contains("warning") && !message.contains("error")
+}
+
+impl Status {
+    // Wandbox does not tell us whether the compilation succeeded, so this guesses it by
+    // whether the program produced any output, or else by the compiler messages.
+    pub(crate) fn classify(
+        status: Option<String>,
+        signal: Option<String>,
+        streams: &Streams,
+    ) -> Status {
+        if let Some(signal) = signal {
+            if TIMEOUT_SIGNALS.contains(&signal.as_str()) {
+                return Status::Timeout;
+            }
+            return Status::Signal(signal);
+        }
+
+        let code = match status.as_ref().map(|s| s.trim().parse()) {
+            Some(Ok(code)) => code,
+            _ => return Status::Unknown(status),
+        };
+
+        if code == 0 {
+            Status::Success
+        } else if streams.is_program_run() {
+            Status::Failure(code)
+        } else if matches!(&streams.compiler_error, Some(m) if !is_warnings_only(m)) {
+            Status::CompileFailure(code)
+        } else {
+            Status::Failure(code)
+        }
+    }
+
+    pub fn is_success(&self) -> bool {
+        *self == Status::Success
+    }
+
+    pub fn is_compiled(&self) -> bool {
+        !matches!(self, Status::CompileFailure(_))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Outcome {
+    pub status: Status,
+    pub compiler: Compiler,
+    // the language, if it was not named explicitly but inferred
+    pub inferred_language: Option<LanguageName>,
+    pub streams: Streams,
+    pub url: Option<String>,
+    // wall-clock time taken by Wandbox
+    pub elapsed: Duration,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn classify(status: Option<&str>, signal: Option<&str>, streams: &Streams) -> Status {
+        Status::classify(
+            status.map(str::to_string),
+            signal.map(str::to_string),
+            streams,
+        )
+    }
+
+    fn compiler_error(message: &str) -> Streams {
+        Streams {
+            compiler_error: Some(message.to_string()),
+            ..Streams::default()
+        }
+    }
+
+    #[test]
+    fn success() {
+        let streams = Streams {
+            program_output: Some("hello\n".to_string()),
+            ..Streams::default()
+        };
+        assert_eq!(classify(Some("0"), None, &streams), Status::Success);
+    }
+
+    #[test]
+    fn compile_error() {
+        let streams = compiler_error("prog.cc:1:1: error: expected unqualified-id\n");
+        assert_eq!(
+            classify(Some("1"), None, &streams),
+            Status::CompileFailure(1)
+        );
+
+        // Go reports errors without saying so
+        let streams = compiler_error("./prog.go:3:2: undefined: x\n");
+        assert_eq!(
+            classify(Some("2"), None, &streams),
+            Status::CompileFailure(2)
+        );
+    }
+
+    #[test]
+    fn warnings_then_failure() {
+        let streams = compiler_error("prog.cc:3:9: warning: unused variable 'x'\n");
+        assert_eq!(classify(Some("1"), None, &streams), Status::Failure(1));
+
+        let streams = Streams {
+            program_error: Some("oops\n".to_string()),
+            ..compiler_error("prog.cc:3:9: warning: unused variable 'x'\n")
+        };
+        assert_eq!(classify(Some("1"), None, &streams), Status::Failure(1));
+    }
+
+    #[test]
+    fn signal() {
+        assert_eq!(
+            classify(None, Some("Segmentation fault"), &Streams::default()),
+            Status::Signal("Segmentation fault".to_string())
+        );
+    }
+
+    #[test]
+    fn timeout() {
+        for &signal in TIMEOUT_SIGNALS {
+            assert_eq!(
+                classify(None, Some(signal), &Streams::default()),
+                Status::Timeout
+            );
+        }
+    }
+
+    #[test]
+    fn missing_status() {
+        assert_eq!(
+            classify(None, None, &Streams::default()),
+            Status::Unknown(None)
+        );
+        assert_eq!(
+            classify(Some("?"), None, &Streams::default()),
+            Status::Unknown(Some("?".to_string()))
+        );
+    }
+}
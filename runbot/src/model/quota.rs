@@ -0,0 +1,75 @@
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuotaSubject {
+    Guild,
+    User,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuotaMetric {
+    Executions,
+    Bytes,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuotaKey {
+    pub subject: QuotaSubject,
+    pub metric: QuotaMetric,
+}
+
+impl QuotaKey {
+    pub const ALL: [QuotaKey; 4] = [
+        QuotaKey::new(QuotaSubject::Guild, QuotaMetric::Executions),
+        QuotaKey::new(QuotaSubject::Guild, QuotaMetric::Bytes),
+        QuotaKey::new(QuotaSubject::User, QuotaMetric::Executions),
+        QuotaKey::new(QuotaSubject::User, QuotaMetric::Bytes),
+    ];
+
+    pub const fn new(subject: QuotaSubject, metric: QuotaMetric) -> QuotaKey {
+        QuotaKey { subject, metric }
+    }
+
+    pub fn name(self) -> &'static str {
+        match (self.subject, self.metric) {
+            (QuotaSubject::Guild, QuotaMetric::Executions) => "guild-executions",
+            (QuotaSubject::Guild, QuotaMetric::Bytes) => "guild-bytes",
+            (QuotaSubject::User, QuotaMetric::Executions) => "user-executions",
+            (QuotaSubject::User, QuotaMetric::Bytes) => "user-bytes",
+        }
+    }
+}
+
+impl fmt::Display for QuotaKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for QuotaKey {
+    type Err = ();
+    fn from_str(s: &str) -> Result<QuotaKey, ()> {
+        QuotaKey::ALL
+            .iter()
+            .find(|k| k.name() == s)
+            .copied()
+            .ok_or(())
+    }
+}
+
+// Consumption within a day
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Usage {
+    pub executions: u64,
+    pub bytes: u64,
+}
+
+impl Usage {
+    pub fn get(&self, metric: QuotaMetric) -> u64 {
+        match metric {
+            QuotaMetric::Executions => self.executions,
+            QuotaMetric::Bytes => self.bytes,
+        }
+    }
+}
@@ -0,0 +1,15 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct RoleID(u64);
+
+impl RoleID {
+    pub fn from_u64(id: u64) -> RoleID {
+        RoleID(id)
+    }
+
+    pub fn as_u64(self) -> u64 {
+        self.0
+    }
+}
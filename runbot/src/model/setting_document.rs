@@ -0,0 +1,118 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+// Portable representation of all settings in a guild.
+// Languages and compilers are referred by their names, and channels by their IDs in string.
+// Values interpreted by the frontend (permissions, output policy, ...) are kept as they are stored.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SettingDocument {
+    pub version: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_channel: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_policy: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub render_style: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hide_warnings: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prefix: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+    // in the form of `{target}:{rule}:{role|user}:{id}`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub access: Vec<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub permission: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub output_limit: BTreeMap<String, u64>,
+    #[serde(default)]
+    pub guild: ScopeSetting,
+    #[serde(default)]
+    pub channels: BTreeMap<String, ScopeSetting>,
+}
+
+impl SettingDocument {
+    pub const VERSION: u32 = 1;
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ScopeSetting {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_save: Option<bool>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub remap: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub options: BTreeMap<String, Vec<String>>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub quota: BTreeMap<String, u64>,
+}
+
+impl ScopeSetting {
+    pub fn is_empty(&self) -> bool {
+        self.auto.is_none()
+            && self.auto_save.is_none()
+            && self.remap.is_empty()
+            && self.options.is_empty()
+            && self.quota.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document() -> SettingDocument {
+        let scope = ScopeSetting {
+            auto: Some(true),
+            auto_save: Some(false),
+            remap: vec![("cpp".to_string(), "clang-head".to_string())]
+                .into_iter()
+                .collect(),
+            options: vec![("clang-head".to_string(), vec!["-O2".to_string()])]
+                .into_iter()
+                .collect(),
+            quota: vec![("user-executions".to_string(), 10)]
+                .into_iter()
+                .collect(),
+        };
+        SettingDocument {
+            version: SettingDocument::VERSION,
+            log_channel: Some("42".to_string()),
+            output_policy: Some("strict".to_string()),
+            render_style: Some("embed".to_string()),
+            hide_warnings: Some(true),
+            prefix: Some("!rb".to_string()),
+            locale: Some("en".to_string()),
+            access: vec!["run:allow:role:7".to_string()],
+            permission: vec![("remap".to_string(), "manage-guild".to_string())]
+                .into_iter()
+                .collect(),
+            output_limit: vec![("lines".to_string(), 20)].into_iter().collect(),
+            guild: scope.clone(),
+            channels: vec![("3".to_string(), scope)].into_iter().collect(),
+        }
+    }
+
+    #[test]
+    fn round_trip_toml() {
+        let document = document();
+        let text = toml::to_string(&document).unwrap();
+        assert_eq!(toml::from_str::<SettingDocument>(&text).unwrap(), document);
+    }
+
+    #[test]
+    fn round_trip_json() {
+        let document = document();
+        let text = serde_json::to_string(&document).unwrap();
+        assert_eq!(
+            serde_json::from_str::<SettingDocument>(&text).unwrap(),
+            document
+        );
+    }
+}
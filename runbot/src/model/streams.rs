@@ -0,0 +1,62 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stream {
+    CompilerOutput,
+    CompilerError,
+    ProgramOutput,
+    ProgramError,
+}
+
+impl Stream {
+    pub const ALL: [Stream; 4] = [
+        Stream::CompilerOutput,
+        Stream::CompilerError,
+        Stream::ProgramOutput,
+        Stream::ProgramError,
+    ];
+
+    pub fn is_compiler(self) -> bool {
+        matches!(self, Stream::CompilerOutput | Stream::CompilerError)
+    }
+}
+
+// Outputs of a run, kept apart by where they come from
+#[derive(Debug, Clone, Default)]
+pub struct Streams {
+    pub compiler_output: Option<String>,
+    pub compiler_error: Option<String>,
+    pub program_output: Option<String>,
+    pub program_error: Option<String>,
+}
+
+impl Streams {
+    pub fn get(&self, stream: Stream) -> Option<&String> {
+        match stream {
+            Stream::CompilerOutput => self.compiler_output.as_ref(),
+            Stream::CompilerError => self.compiler_error.as_ref(),
+            Stream::ProgramOutput => self.program_output.as_ref(),
+            Stream::ProgramError => self.program_error.as_ref(),
+        }
+    }
+
+    pub fn get_mut(&mut self, stream: Stream) -> &mut Option<String> {
+        match stream {
+            Stream::CompilerOutput => &mut self.compiler_output,
+            Stream::CompilerError => &mut self.compiler_error,
+            Stream::ProgramOutput => &mut self.program_output,
+            Stream::ProgramError => &mut self.program_error,
+        }
+    }
+
+    pub fn map(&self, mut f: impl FnMut(Stream, &String) -> String) -> Streams {
+        let mut streams = Streams::default();
+        for &stream in &Stream::ALL {
+            *streams.get_mut(stream) = self.get(stream).map(|s| f(stream, s));
+        }
+        streams
+    }
+
+    // the program is run only when the compilation succeeded
+    pub fn is_program_run(&self) -> bool {
+        self.program_output.is_some() || self.program_error.is_some()
+    }
+}
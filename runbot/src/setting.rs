@@ -1,24 +1,57 @@
+mod audit;
+mod cache;
+
 use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 
+use crate::model::audit::AuditScope;
 use crate::model::channel_id::ChannelID;
 use crate::model::compiler::CompilerID;
 use crate::model::guild_id::GuildID;
 use crate::model::language::LanguageID;
+use crate::model::quota::Usage;
+use crate::model::user_id::UserID;
 use crate::Result;
 
 use parking_lot::Mutex;
 use redis::Commands;
 
+use audit::stored_string;
+pub(crate) use audit::Record;
+pub use cache::SettingCache;
+use cache::{ChannelSetting, GuildSetting, UserSetting};
+
+// the number of audit records kept per guild
+const AUDIT_LOG_LENGTH: isize = 1000;
+
+// usage counters are keyed by day, and outlive the day just long enough to be reported
+const USAGE_EXPIRY_SECS: usize = 2 * 24 * 60 * 60;
+
+// days since the UNIX epoch, in UTC
+fn today() -> u64 {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    now.as_secs() / (24 * 60 * 60)
+}
+
 // Data Access Object for Runbot settings
+// Every mutation is recorded in the audit log as performed by `actor`.
 pub struct Setting {
     conn: Arc<Mutex<redis::Connection>>,
     prefix: String,
+    cache: SettingCache,
+    actor: UserID,
+    recorded: Mutex<Vec<Record>>,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Scope {
     Channel(ChannelID),
     Guild,
+    User(UserID),
+    // the user's settings in direct messages, kept apart from those applied in guilds
+    Direct(UserID),
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -26,6 +59,17 @@ enum Field {
     Auto,
     AutoSave,
     Remap,
+    Options,
+    LogChannel,
+    Permission,
+    Access,
+    Quota,
+    OutputPolicy,
+    OutputLimit,
+    RenderStyle,
+    HideWarnings,
+    Prefix,
+    Locale,
 }
 
 impl Field {
@@ -34,23 +78,55 @@ impl Field {
             Field::Auto => "auto",
             Field::AutoSave => "auto_save",
             Field::Remap => "remap",
+            Field::Options => "options",
+            Field::LogChannel => "log_channel",
+            Field::Permission => "permission",
+            Field::Access => "access",
+            Field::Quota => "quota",
+            Field::OutputPolicy => "output_policy",
+            Field::OutputLimit => "output_limit",
+            Field::RenderStyle => "render_style",
+            Field::HideWarnings => "hide_warnings",
+            Field::Prefix => "prefix",
+            Field::Locale => "locale",
         }
     }
 }
 
+// Changing the key layout requires a migration in `crate::migration`
 #[derive(Debug, Clone)]
 enum Key<'a> {
-    ChannelKey {
+    Channel {
         prefix: &'a str,
         guild_id: GuildID,
         channel_id: ChannelID,
         field: Field,
     },
-    DefaultKey {
+    Default {
         prefix: &'a str,
         guild_id: GuildID,
         field: Field,
     },
+    User {
+        prefix: &'a str,
+        user_id: UserID,
+        field: Field,
+    },
+    Direct {
+        prefix: &'a str,
+        user_id: UserID,
+        field: Field,
+    },
+    Audit {
+        prefix: &'a str,
+        guild_id: GuildID,
+    },
+    Usage {
+        prefix: &'a str,
+        day: u64,
+        guild_id: GuildID,
+        user_id: Option<UserID>,
+    },
 }
 
 impl redis::ToRedisArgs for Key<'_> {
@@ -59,7 +135,7 @@ impl redis::ToRedisArgs for Key<'_> {
         W: redis::RedisWrite,
     {
         let key_name = match self {
-            Key::ChannelKey {
+            Key::Channel {
                 prefix,
                 guild_id,
                 channel_id,
@@ -71,15 +147,39 @@ impl redis::ToRedisArgs for Key<'_> {
                 channel_id.as_u64(),
                 field.name()
             ),
-            Key::DefaultKey {
+            Key::Default {
                 prefix,
                 guild_id,
                 field,
+            } => format!("{}:guild:{}:{}", prefix, guild_id.as_u64(), field.name()),
+            Key::User {
+                prefix,
+                user_id,
+                field,
+            } => format!("{}:user:{}:{}", prefix, user_id.as_u64(), field.name()),
+            Key::Direct {
+                prefix,
+                user_id,
+                field,
+            } => format!("{}:direct:{}:{}", prefix, user_id.as_u64(), field.name()),
+            Key::Audit { prefix, guild_id } => format!("{}:audit:{}", prefix, guild_id.as_u64()),
+            Key::Usage {
+                prefix,
+                day,
+                guild_id,
+                user_id: None,
+            } => format!("{}:usage:{}:{}", prefix, day, guild_id.as_u64()),
+            Key::Usage {
+                prefix,
+                day,
+                guild_id,
+                user_id: Some(user_id),
             } => format!(
-                "{}:channel:{}:default:{}",
+                "{}:usage:{}:{}:user:{}",
                 prefix,
+                day,
                 guild_id.as_u64(),
-                field.name()
+                user_id.as_u64()
             ),
         };
 
@@ -93,6 +193,10 @@ enum ScanPattern<'a> {
         guild_id: GuildID,
         field: Field,
     },
+    AllChannelFields {
+        prefix: &'a str,
+        guild_id: GuildID,
+    },
 }
 
 impl redis::ToRedisArgs for ScanPattern<'_> {
@@ -112,13 +216,166 @@ impl redis::ToRedisArgs for ScanPattern<'_> {
                 field.name()
             )
             .write_redis_args(out),
+            ScanPattern::AllChannelFields { prefix, guild_id } => {
+                format!("{}:channel:{}:*", prefix, guild_id.as_u64()).write_redis_args(out)
+            }
         }
     }
 }
 
 impl Setting {
-    pub fn new(conn: Arc<Mutex<redis::Connection>>, prefix: String) -> Setting {
-        Setting { conn, prefix }
+    pub fn new(
+        conn: Arc<Mutex<redis::Connection>>,
+        prefix: String,
+        cache: SettingCache,
+        actor: UserID,
+    ) -> Setting {
+        Setting {
+            conn,
+            prefix,
+            cache,
+            actor,
+            recorded: Mutex::new(Vec::new()),
+        }
+    }
+
+    fn record(
+        &self,
+        guild_id: GuildID,
+        scope: Scope,
+        field: Field,
+        hash_key: Option<String>,
+        old: Option<String>,
+        new: Option<String>,
+    ) -> Result<()> {
+        // personal preferences are not guild configuration, and are kept private
+        let scope = match scope {
+            Scope::Channel(channel_id) => AuditScope::Channel(channel_id),
+            Scope::Guild => AuditScope::Guild,
+            Scope::User(_) | Scope::Direct(_) => return Ok(()),
+        };
+        let record = Record::new(self.actor, scope, field.name(), hash_key, old, new);
+
+        let key = Key::Audit {
+            prefix: &self.prefix,
+            guild_id,
+        };
+        // direct messages have no one to audit them
+        if guild_id != GuildID::DIRECT {
+            let mut conn = self.conn.lock();
+            conn.lpush(key.clone(), record.clone())?;
+            conn.ltrim(key, 0, AUDIT_LOG_LENGTH - 1)?;
+        }
+
+        self.recorded.lock().push(record);
+        Ok(())
+    }
+
+    // Called after a write. Values read before this are not cached, see `SettingCache::generation`.
+    fn invalidate(&self, guild_id: GuildID, scope: Scope) {
+        match scope {
+            Scope::Channel(channel_id) => self.cache.invalidate_channel(guild_id, channel_id),
+            Scope::Guild => self.cache.invalidate_guild(guild_id),
+            Scope::User(_) | Scope::Direct(_) => self.cache.invalidate_personal(scope),
+        }
+    }
+
+    fn channel_setting(
+        &self,
+        guild_id: GuildID,
+        channel_id: ChannelID,
+    ) -> Result<Arc<ChannelSetting>> {
+        if let Some(setting) = self.cache.get_channel(guild_id, channel_id) {
+            return Ok(setting);
+        }
+        let generation = self.cache.generation();
+
+        let auto: u32 = self
+            .get_simple(guild_id, channel_id, Field::Auto)?
+            .unwrap_or(1);
+        let auto_save: u32 = self
+            .get_simple(guild_id, channel_id, Field::AutoSave)?
+            .unwrap_or(0);
+        let remap = self.get_hash_all(guild_id, channel_id, Field::Remap)?;
+        let options = self.get_hash_all(guild_id, channel_id, Field::Options)?;
+
+        let setting = ChannelSetting {
+            auto: auto != 0,
+            auto_save: auto_save != 0,
+            remap: remap.into_iter().collect(),
+            options: options.into_iter().collect(),
+        };
+        Ok(self
+            .cache
+            .put_channel(guild_id, channel_id, setting, generation))
+    }
+
+    fn guild_setting(&self, guild_id: GuildID) -> Result<Arc<GuildSetting>> {
+        if let Some(setting) = self.cache.get_guild(guild_id) {
+            return Ok(setting);
+        }
+        let generation = self.cache.generation();
+
+        let prefix = self.get_exact_simple(guild_id, Scope::Guild, Field::Prefix)?;
+        let locale = self.get_exact_simple(guild_id, Scope::Guild, Field::Locale)?;
+        let access: Vec<(String, u32)> =
+            self.get_exact_hash_all(guild_id, Scope::Guild, Field::Access)?;
+
+        let setting = GuildSetting {
+            prefix,
+            locale,
+            access: access.into_iter().map(|(k, _)| k).collect(),
+        };
+        Ok(self.cache.put_guild(guild_id, setting, generation))
+    }
+
+    // `scope` is either `Scope::User` or `Scope::Direct`
+    fn user_setting(&self, scope: Scope) -> Result<Arc<UserSetting>> {
+        if let Some(setting) = self.cache.get_personal(scope) {
+            return Ok(setting);
+        }
+        let generation = self.cache.generation();
+
+        let auto: Option<u32> = self.get_user_simple(scope, Field::Auto)?;
+        let auto_save: Option<u32> = self.get_user_simple(scope, Field::AutoSave)?;
+        let remap = self.get_user_hash_all(scope, Field::Remap)?;
+        let options = self.get_user_hash_all(scope, Field::Options)?;
+        let locale = self.get_user_simple(scope, Field::Locale)?;
+
+        let setting = UserSetting {
+            auto: auto.map(|d| d != 0),
+            auto_save: auto_save.map(|d| d != 0),
+            remap: remap.into_iter().collect(),
+            options: options.into_iter().collect(),
+            locale,
+        };
+        Ok(self.cache.put_personal(scope, setting, generation))
+    }
+
+    fn key(&self, guild_id: GuildID, scope: Scope, field: Field) -> Key {
+        match scope {
+            Scope::Channel(channel_id) => Key::Channel {
+                prefix: &self.prefix,
+                guild_id,
+                channel_id,
+                field,
+            },
+            Scope::Guild => Key::Default {
+                prefix: &self.prefix,
+                guild_id,
+                field,
+            },
+            Scope::User(user_id) => Key::User {
+                prefix: &self.prefix,
+                user_id,
+                field,
+            },
+            Scope::Direct(user_id) => Key::Direct {
+                prefix: &self.prefix,
+                user_id,
+                field,
+            },
+        }
     }
 
     fn get_key(
@@ -127,13 +384,13 @@ impl Setting {
         channel_id: ChannelID,
         field: Field,
     ) -> Result<Option<Key>> {
-        let channel_key = Key::ChannelKey {
+        let channel_key = Key::Channel {
             prefix: &self.prefix,
             guild_id,
             channel_id,
             field,
         };
-        let default_key = Key::DefaultKey {
+        let default_key = Key::Default {
             prefix: &self.prefix,
             guild_id,
             field,
@@ -164,21 +421,6 @@ impl Setting {
         Ok(Some(value))
     }
 
-    fn get_hash<K: redis::ToRedisArgs, V: redis::FromRedisValue>(
-        &self,
-        guild_id: GuildID,
-        channel_id: ChannelID,
-        field: Field,
-        hash_key: K,
-    ) -> Result<Option<V>> {
-        let key = match self.get_key(guild_id, channel_id, field)? {
-            Some(k) => k,
-            None => return Ok(None),
-        };
-        let value = self.conn.lock().hget(key, hash_key)?;
-        Ok(value)
-    }
-
     fn get_hash_all<K: redis::FromRedisValue, V: redis::FromRedisValue>(
         &self,
         guild_id: GuildID,
@@ -197,40 +439,33 @@ impl Setting {
     where
         V: redis::ToRedisArgs + Copy,
     {
-        match scope {
-            Scope::Channel(channel_id) => {
-                let key = Key::ChannelKey {
-                    prefix: &self.prefix,
-                    guild_id,
-                    channel_id,
-                    field,
-                };
-                self.conn.lock().set(key, value)?;
-            }
-            Scope::Guild => {
-                let mut conn = self.conn.lock();
-
-                let keys: Vec<Vec<u8>> = conn
-                    .scan_match::<_, Vec<u8>>(ScanPattern::AllChannels {
-                        prefix: &self.prefix,
-                        guild_id,
-                        field,
-                    })?
-                    .collect();
-
-                for k in keys {
-                    conn.set(k, value)?;
-                }
+        let new = stored_string(&value);
 
-                let default_key = Key::DefaultKey {
-                    prefix: &self.prefix,
-                    guild_id,
-                    field,
-                };
-                conn.set(default_key, value)?;
+        // guild defaults override the channels, whose old values are recorded as well
+        let mut changes = Vec::new();
+        if let Scope::Guild = scope {
+            for channel_id in self.list_channels_with(guild_id, field)? {
+                let channel = Scope::Channel(channel_id);
+                changes.push((channel, self.get_exact_simple(guild_id, channel, field)?));
             }
         }
+        changes.push((scope, self.get_exact_simple(guild_id, scope, field)?));
 
+        for &(scope, _) in &changes {
+            self.conn
+                .lock()
+                .set(self.key(guild_id, scope, field), value)?;
+        }
+
+        self.invalidate(guild_id, scope);
+        for (scope, old) in changes {
+            if let Scope::Channel(_) = scope {
+                if old.as_ref() == Some(&new) {
+                    continue;
+                }
+            }
+            self.record(guild_id, scope, field, None, old, Some(new.clone()))?;
+        }
         Ok(())
     }
 
@@ -246,53 +481,181 @@ impl Setting {
         K: redis::ToRedisArgs + Copy,
         V: redis::ToRedisArgs + Copy,
     {
-        match scope {
-            Scope::Channel(channel_id) => {
-                let key = Key::ChannelKey {
-                    prefix: &self.prefix,
-                    guild_id,
-                    channel_id,
-                    field,
-                };
-                self.conn.lock().hset(key, hash_key, hash_value)?;
-            }
-            Scope::Guild => {
-                let mut conn = self.conn.lock();
-
-                let keys: Vec<Vec<u8>> = conn
-                    .scan_match::<_, Vec<u8>>(ScanPattern::AllChannels {
-                        prefix: &self.prefix,
-                        guild_id,
-                        field,
-                    })?
-                    .collect();
-
-                for k in keys {
-                    conn.hset(k, hash_key, hash_value)?;
-                }
+        let new = stored_string(&hash_value);
 
-                let default_key = Key::DefaultKey {
-                    prefix: &self.prefix,
-                    guild_id,
-                    field,
-                };
-                conn.hset(default_key, hash_key, hash_value)?;
+        // guild defaults override the channels, whose old values are recorded as well
+        let mut changes = Vec::new();
+        if let Scope::Guild = scope {
+            for channel_id in self.list_channels_with(guild_id, field)? {
+                let channel = Scope::Channel(channel_id);
+                let old = self.get_exact_hash(guild_id, channel, field, hash_key)?;
+                changes.push((channel, old));
             }
         }
+        changes.push((
+            scope,
+            self.get_exact_hash(guild_id, scope, field, hash_key)?,
+        ));
+
+        for &(scope, _) in &changes {
+            self.conn
+                .lock()
+                .hset(self.key(guild_id, scope, field), hash_key, hash_value)?;
+        }
 
+        self.invalidate(guild_id, scope);
+        for (scope, old) in changes {
+            if let Scope::Channel(_) = scope {
+                if old.as_ref() == Some(&new) {
+                    continue;
+                }
+            }
+            self.record(
+                guild_id,
+                scope,
+                field,
+                Some(stored_string(&hash_key)),
+                old,
+                Some(new.clone()),
+            )?;
+        }
         Ok(())
     }
 
+    // keys of personal settings do not depend on the guild
+    fn get_user_simple<V: redis::FromRedisValue>(
+        &self,
+        scope: Scope,
+        field: Field,
+    ) -> Result<Option<V>> {
+        self.get_exact_simple(GuildID::DIRECT, scope, field)
+    }
+
+    fn get_user_hash_all<K: redis::FromRedisValue, V: redis::FromRedisValue>(
+        &self,
+        scope: Scope,
+        field: Field,
+    ) -> Result<Vec<(K, V)>> {
+        self.get_exact_hash_all(GuildID::DIRECT, scope, field)
+    }
+
+    // "exact" accessors read and write only the key of the given scope,
+    // without falling back to the defaults nor propagating to channels
+    fn get_exact_simple<V: redis::FromRedisValue>(
+        &self,
+        guild_id: GuildID,
+        scope: Scope,
+        field: Field,
+    ) -> Result<Option<V>> {
+        let value = self.conn.lock().get(self.key(guild_id, scope, field))?;
+        Ok(value)
+    }
+
+    fn get_exact_hash<K: redis::ToRedisArgs, V: redis::FromRedisValue>(
+        &self,
+        guild_id: GuildID,
+        scope: Scope,
+        field: Field,
+        hash_key: K,
+    ) -> Result<Option<V>> {
+        let value = self
+            .conn
+            .lock()
+            .hget(self.key(guild_id, scope, field), hash_key)?;
+        Ok(value)
+    }
+
+    fn get_exact_hash_all<K: redis::FromRedisValue, V: redis::FromRedisValue>(
+        &self,
+        guild_id: GuildID,
+        scope: Scope,
+        field: Field,
+    ) -> Result<Vec<(K, V)>> {
+        let value = self.conn.lock().hgetall(self.key(guild_id, scope, field))?;
+        Ok(value)
+    }
+
+    fn set_exact_simple<V: redis::ToRedisArgs + Copy>(
+        &self,
+        guild_id: GuildID,
+        scope: Scope,
+        field: Field,
+        value: V,
+    ) -> Result<()> {
+        let old = self.get_exact_simple(guild_id, scope, field)?;
+        self.conn
+            .lock()
+            .set(self.key(guild_id, scope, field), value)?;
+        self.invalidate(guild_id, scope);
+        self.record(
+            guild_id,
+            scope,
+            field,
+            None,
+            old,
+            Some(stored_string(&value)),
+        )
+    }
+
+    fn unset_exact_simple(&self, guild_id: GuildID, scope: Scope, field: Field) -> Result<()> {
+        let old = self.get_exact_simple(guild_id, scope, field)?;
+        self.conn.lock().del(self.key(guild_id, scope, field))?;
+        self.invalidate(guild_id, scope);
+        self.record(guild_id, scope, field, None, old, None)
+    }
+
+    fn unset_exact_hash<K: redis::ToRedisArgs + Copy>(
+        &self,
+        guild_id: GuildID,
+        scope: Scope,
+        field: Field,
+        hash_key: K,
+    ) -> Result<()> {
+        let old = self.get_exact_hash(guild_id, scope, field, hash_key)?;
+        self.conn
+            .lock()
+            .hdel(self.key(guild_id, scope, field), hash_key)?;
+        self.invalidate(guild_id, scope);
+        self.record(
+            guild_id,
+            scope,
+            field,
+            Some(stored_string(&hash_key)),
+            old,
+            None,
+        )
+    }
+
+    fn set_exact_hash<K: redis::ToRedisArgs + Copy, V: redis::ToRedisArgs + Copy>(
+        &self,
+        guild_id: GuildID,
+        scope: Scope,
+        field: Field,
+        hash_key: K,
+        hash_value: V,
+    ) -> Result<()> {
+        let old = self.get_exact_hash(guild_id, scope, field, hash_key)?;
+        self.conn
+            .lock()
+            .hset(self.key(guild_id, scope, field), hash_key, hash_value)?;
+        self.invalidate(guild_id, scope);
+        self.record(
+            guild_id,
+            scope,
+            field,
+            Some(stored_string(&hash_key)),
+            old,
+            Some(stored_string(&hash_value)),
+        )
+    }
+
     // public interface
     pub fn set_auto(&self, guild_id: GuildID, scope: Scope, set: bool) -> Result<()> {
         self.set_simple(guild_id, scope, Field::Auto, set as u32)
     }
 
     pub fn get_auto(&self, guild_id: GuildID, channel_id: ChannelID) -> Result<bool> {
-        let data: u32 = self
-            .get_simple(guild_id, channel_id, Field::Auto)?
-            .unwrap_or(1);
-        Ok(data != 0)
+        Ok(self.channel_setting(guild_id, channel_id)?.auto)
     }
 
     pub fn set_auto_save(&self, guild_id: GuildID, scope: Scope, set: bool) -> Result<()> {
@@ -300,10 +663,7 @@ impl Setting {
     }
 
     pub fn get_auto_save(&self, guild_id: GuildID, channel_id: ChannelID) -> Result<bool> {
-        let data: u32 = self
-            .get_simple(guild_id, channel_id, Field::AutoSave)?
-            .unwrap_or(0);
-        Ok(data != 0)
+        Ok(self.channel_setting(guild_id, channel_id)?.auto_save)
     }
 
     pub fn set_remap(
@@ -322,7 +682,8 @@ impl Setting {
         channel_id: ChannelID,
         language_id: LanguageID,
     ) -> Result<Option<CompilerID>> {
-        self.get_hash(guild_id, channel_id, Field::Remap, language_id)
+        let setting = self.channel_setting(guild_id, channel_id)?;
+        Ok(setting.remap.get(&language_id).copied())
     }
 
     pub fn get_remap_all(
@@ -330,6 +691,418 @@ impl Setting {
         guild_id: GuildID,
         channel_id: ChannelID,
     ) -> Result<Vec<(LanguageID, CompilerID)>> {
-        self.get_hash_all(guild_id, channel_id, Field::Remap)
+        let setting = self.channel_setting(guild_id, channel_id)?;
+        Ok(setting.remap.iter().map(|(&l, &c)| (l, c)).collect())
+    }
+
+    pub fn set_options(
+        &self,
+        guild_id: GuildID,
+        scope: Scope,
+        compiler_id: CompilerID,
+        options: &str,
+    ) -> Result<()> {
+        self.set_hash(guild_id, scope, Field::Options, compiler_id, options)
+    }
+
+    pub fn get_options(
+        &self,
+        guild_id: GuildID,
+        channel_id: ChannelID,
+        compiler_id: CompilerID,
+    ) -> Result<Option<String>> {
+        let setting = self.channel_setting(guild_id, channel_id)?;
+        Ok(setting.options.get(&compiler_id).cloned())
+    }
+
+    pub fn get_options_all(
+        &self,
+        guild_id: GuildID,
+        channel_id: ChannelID,
+    ) -> Result<Vec<(CompilerID, String)>> {
+        let setting = self.channel_setting(guild_id, channel_id)?;
+        Ok(setting
+            .options
+            .iter()
+            .map(|(&c, o)| (c, o.clone()))
+            .collect())
+    }
+
+    // user overrides of `Scope::User` or `Scope::Direct`; `None` means that the user has no preference
+    pub fn get_user_auto(&self, scope: Scope) -> Result<Option<bool>> {
+        Ok(self.user_setting(scope)?.auto)
+    }
+
+    pub fn get_user_auto_save(&self, scope: Scope) -> Result<Option<bool>> {
+        Ok(self.user_setting(scope)?.auto_save)
+    }
+
+    pub fn get_user_remap(
+        &self,
+        scope: Scope,
+        language_id: LanguageID,
+    ) -> Result<Option<CompilerID>> {
+        Ok(self.user_setting(scope)?.remap.get(&language_id).copied())
+    }
+
+    pub fn get_user_remap_all(&self, scope: Scope) -> Result<Vec<(LanguageID, CompilerID)>> {
+        let setting = self.user_setting(scope)?;
+        Ok(setting.remap.iter().map(|(&l, &c)| (l, c)).collect())
+    }
+
+    pub fn get_user_options(
+        &self,
+        scope: Scope,
+        compiler_id: CompilerID,
+    ) -> Result<Option<String>> {
+        Ok(self.user_setting(scope)?.options.get(&compiler_id).cloned())
+    }
+
+    pub fn get_user_options_all(&self, scope: Scope) -> Result<Vec<(CompilerID, String)>> {
+        let setting = self.user_setting(scope)?;
+        Ok(setting
+            .options
+            .iter()
+            .map(|(&c, o)| (c, o.clone()))
+            .collect())
+    }
+
+    pub fn clear_user(&self, guild_id: GuildID, scope: Scope) -> Result<()> {
+        for &field in &[
+            Field::Auto,
+            Field::AutoSave,
+            Field::Remap,
+            Field::Options,
+            Field::Locale,
+        ] {
+            let key = self.key(guild_id, scope, field);
+            if self.conn.lock().exists(key.clone())? {
+                self.conn.lock().del(key)?;
+                self.invalidate(guild_id, scope);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn list_channels(&self, guild_id: GuildID) -> Result<Vec<ChannelID>> {
+        self.scan_channels(
+            guild_id,
+            ScanPattern::AllChannelFields {
+                prefix: &self.prefix,
+                guild_id,
+            },
+        )
+    }
+
+    // channels which have their own value of `field`
+    fn list_channels_with(&self, guild_id: GuildID, field: Field) -> Result<Vec<ChannelID>> {
+        self.scan_channels(
+            guild_id,
+            ScanPattern::AllChannels {
+                prefix: &self.prefix,
+                guild_id,
+                field,
+            },
+        )
+    }
+
+    fn scan_channels(&self, guild_id: GuildID, pattern: ScanPattern) -> Result<Vec<ChannelID>> {
+        let key_prefix = format!("{}:channel:{}:", self.prefix, guild_id.as_u64());
+        let keys: Vec<String> = self.conn.lock().scan_match::<_, String>(pattern)?.collect();
+
+        let mut ids: Vec<u64> = keys
+            .iter()
+            .filter_map(|k| k.strip_prefix(&key_prefix))
+            .filter_map(|k| k.split(':').next())
+            .filter_map(|id| id.parse().ok())
+            .collect();
+        ids.sort_unstable();
+        ids.dedup();
+
+        Ok(ids.into_iter().map(ChannelID::from_u64).collect())
+    }
+
+    pub fn get_exact_auto(&self, guild_id: GuildID, scope: Scope) -> Result<Option<bool>> {
+        let data: Option<u32> = self.get_exact_simple(guild_id, scope, Field::Auto)?;
+        Ok(data.map(|d| d != 0))
+    }
+
+    pub fn set_exact_auto(&self, guild_id: GuildID, scope: Scope, set: bool) -> Result<()> {
+        self.set_exact_simple(guild_id, scope, Field::Auto, set as u32)
+    }
+
+    pub fn get_exact_auto_save(&self, guild_id: GuildID, scope: Scope) -> Result<Option<bool>> {
+        let data: Option<u32> = self.get_exact_simple(guild_id, scope, Field::AutoSave)?;
+        Ok(data.map(|d| d != 0))
+    }
+
+    pub fn set_exact_auto_save(&self, guild_id: GuildID, scope: Scope, set: bool) -> Result<()> {
+        self.set_exact_simple(guild_id, scope, Field::AutoSave, set as u32)
+    }
+
+    pub fn get_exact_remap_all(
+        &self,
+        guild_id: GuildID,
+        scope: Scope,
+    ) -> Result<Vec<(LanguageID, CompilerID)>> {
+        self.get_exact_hash_all(guild_id, scope, Field::Remap)
+    }
+
+    pub fn set_exact_remap(
+        &self,
+        guild_id: GuildID,
+        scope: Scope,
+        language_id: LanguageID,
+        compiler_id: CompilerID,
+    ) -> Result<()> {
+        self.set_exact_hash(guild_id, scope, Field::Remap, language_id, compiler_id)
+    }
+
+    pub fn get_exact_options_all(
+        &self,
+        guild_id: GuildID,
+        scope: Scope,
+    ) -> Result<Vec<(CompilerID, String)>> {
+        self.get_exact_hash_all(guild_id, scope, Field::Options)
+    }
+
+    pub fn set_exact_options(
+        &self,
+        guild_id: GuildID,
+        scope: Scope,
+        compiler_id: CompilerID,
+        options: &str,
+    ) -> Result<()> {
+        self.set_exact_hash(guild_id, scope, Field::Options, compiler_id, options)
+    }
+
+    pub fn set_log_channel(&self, guild_id: GuildID, channel_id: Option<ChannelID>) -> Result<()> {
+        match channel_id {
+            Some(id) => {
+                self.set_exact_simple(guild_id, Scope::Guild, Field::LogChannel, id.as_u64())
+            }
+            None => self.unset_exact_simple(guild_id, Scope::Guild, Field::LogChannel),
+        }
+    }
+
+    pub fn get_log_channel(&self, guild_id: GuildID) -> Result<Option<ChannelID>> {
+        let id: Option<u64> = self.get_exact_simple(guild_id, Scope::Guild, Field::LogChannel)?;
+        Ok(id.map(ChannelID::from_u64))
+    }
+
+    // required permissions are stored by command names, in a platform-specific representation
+    pub fn set_permission(
+        &self,
+        guild_id: GuildID,
+        command: &str,
+        permission: Option<&str>,
+    ) -> Result<()> {
+        match permission {
+            Some(p) => self.set_exact_hash(guild_id, Scope::Guild, Field::Permission, command, p),
+            None => self.unset_exact_hash(guild_id, Scope::Guild, Field::Permission, command),
+        }
+    }
+
+    pub fn get_permission(&self, guild_id: GuildID, command: &str) -> Result<Option<String>> {
+        self.get_exact_hash(guild_id, Scope::Guild, Field::Permission, command)
+    }
+
+    pub fn get_permission_all(&self, guild_id: GuildID) -> Result<Vec<(String, String)>> {
+        self.get_exact_hash_all(guild_id, Scope::Guild, Field::Permission)
+    }
+
+    // the policy is stored in a platform-specific representation
+    pub fn set_output_policy(&self, guild_id: GuildID, policy: Option<&str>) -> Result<()> {
+        match policy {
+            Some(p) => self.set_exact_simple(guild_id, Scope::Guild, Field::OutputPolicy, p),
+            None => self.unset_exact_simple(guild_id, Scope::Guild, Field::OutputPolicy),
+        }
+    }
+
+    pub fn get_output_policy(&self, guild_id: GuildID) -> Result<Option<String>> {
+        self.get_exact_simple(guild_id, Scope::Guild, Field::OutputPolicy)
+    }
+
+    pub fn set_render_style(&self, guild_id: GuildID, style: Option<&str>) -> Result<()> {
+        match style {
+            Some(s) => self.set_exact_simple(guild_id, Scope::Guild, Field::RenderStyle, s),
+            None => self.unset_exact_simple(guild_id, Scope::Guild, Field::RenderStyle),
+        }
+    }
+
+    pub fn get_render_style(&self, guild_id: GuildID) -> Result<Option<String>> {
+        self.get_exact_simple(guild_id, Scope::Guild, Field::RenderStyle)
+    }
+
+    pub fn set_prefix(&self, guild_id: GuildID, prefix: Option<&str>) -> Result<()> {
+        match prefix {
+            Some(p) => self.set_exact_simple(guild_id, Scope::Guild, Field::Prefix, p),
+            None => self.unset_exact_simple(guild_id, Scope::Guild, Field::Prefix),
+        }
+    }
+
+    pub fn get_prefix(&self, guild_id: GuildID) -> Result<Option<String>> {
+        Ok(self.guild_setting(guild_id)?.prefix.clone())
+    }
+
+    pub fn set_locale(&self, guild_id: GuildID, scope: Scope, locale: Option<&str>) -> Result<()> {
+        match locale {
+            Some(l) => self.set_exact_simple(guild_id, scope, Field::Locale, l),
+            None => self.unset_exact_simple(guild_id, scope, Field::Locale),
+        }
+    }
+
+    pub fn get_locale(&self, guild_id: GuildID) -> Result<Option<String>> {
+        Ok(self.guild_setting(guild_id)?.locale.clone())
+    }
+
+    pub fn get_user_locale(&self, scope: Scope) -> Result<Option<String>> {
+        Ok(self.user_setting(scope)?.locale.clone())
+    }
+
+    pub fn set_hide_warnings(&self, guild_id: GuildID, set: bool) -> Result<()> {
+        self.set_exact_simple(guild_id, Scope::Guild, Field::HideWarnings, set as u32)
+    }
+
+    pub fn get_hide_warnings(&self, guild_id: GuildID) -> Result<bool> {
+        Ok(self.get_exact_hide_warnings(guild_id)?.unwrap_or(false))
+    }
+
+    pub fn get_exact_hide_warnings(&self, guild_id: GuildID) -> Result<Option<bool>> {
+        let value: Option<u32> =
+            self.get_exact_simple(guild_id, Scope::Guild, Field::HideWarnings)?;
+        Ok(value.map(|v| v != 0))
+    }
+
+    // limits are stored by names, interpreted by the frontend
+    pub fn set_output_limit(
+        &self,
+        guild_id: GuildID,
+        name: &str,
+        limit: Option<u64>,
+    ) -> Result<()> {
+        match limit {
+            Some(l) => self.set_exact_hash(guild_id, Scope::Guild, Field::OutputLimit, name, l),
+            None => self.unset_exact_hash(guild_id, Scope::Guild, Field::OutputLimit, name),
+        }
+    }
+
+    pub fn get_output_limit_all(&self, guild_id: GuildID) -> Result<Vec<(String, u64)>> {
+        self.get_exact_hash_all(guild_id, Scope::Guild, Field::OutputLimit)
+    }
+
+    pub fn add_access(&self, guild_id: GuildID, entry: &str) -> Result<()> {
+        self.set_exact_hash(guild_id, Scope::Guild, Field::Access, entry, 1)
+    }
+
+    pub fn remove_access(&self, guild_id: GuildID, entry: &str) -> Result<()> {
+        self.unset_exact_hash(guild_id, Scope::Guild, Field::Access, entry)
+    }
+
+    pub fn get_access_all(&self, guild_id: GuildID) -> Result<Vec<String>> {
+        Ok(self.guild_setting(guild_id)?.access.clone())
+    }
+
+    pub fn set_quota(
+        &self,
+        guild_id: GuildID,
+        scope: Scope,
+        key: &str,
+        limit: Option<u64>,
+    ) -> Result<()> {
+        match limit {
+            Some(l) => self.set_exact_hash(guild_id, scope, Field::Quota, key, l),
+            None => self.unset_exact_hash(guild_id, scope, Field::Quota, key),
+        }
+    }
+
+    pub fn get_quota(&self, guild_id: GuildID, scope: Scope, key: &str) -> Result<Option<u64>> {
+        self.get_exact_hash(guild_id, scope, Field::Quota, key)
+    }
+
+    pub fn get_quota_all(&self, guild_id: GuildID, scope: Scope) -> Result<Vec<(String, u64)>> {
+        self.get_exact_hash_all(guild_id, scope, Field::Quota)
+    }
+
+    fn usage_key(&self, guild_id: GuildID, user_id: Option<UserID>) -> Key {
+        Key::Usage {
+            prefix: &self.prefix,
+            day: today(),
+            guild_id,
+            user_id,
+        }
+    }
+
+    // Counts one execution of `bytes` bytes against both the guild and the user,
+    // and returns the usages of the guild and the user including it
+    pub fn add_usage(
+        &self,
+        guild_id: GuildID,
+        user_id: UserID,
+        bytes: u64,
+    ) -> Result<(Usage, Usage)> {
+        self.incr_usage(guild_id, user_id, 1, bytes as i64)
+    }
+
+    // takes back an execution counted by `add_usage`
+    pub fn remove_usage(&self, guild_id: GuildID, user_id: UserID, bytes: u64) -> Result<()> {
+        self.incr_usage(guild_id, user_id, -1, -(bytes as i64))?;
+        Ok(())
+    }
+
+    fn incr_usage(
+        &self,
+        guild_id: GuildID,
+        user_id: UserID,
+        executions: i64,
+        bytes: i64,
+    ) -> Result<(Usage, Usage)> {
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+        for &user_id in &[None, Some(user_id)] {
+            let key = self.usage_key(guild_id, user_id);
+            pipe.hincr(key.clone(), "executions", executions)
+                .hincr(key.clone(), "bytes", bytes)
+                .expire(key, USAGE_EXPIRY_SECS)
+                .ignore();
+        }
+        let (guild_executions, guild_bytes, user_executions, user_bytes): (u64, u64, u64, u64) =
+            pipe.query(&mut *self.conn.lock())?;
+        let guild = Usage {
+            executions: guild_executions,
+            bytes: guild_bytes,
+        };
+        let user = Usage {
+            executions: user_executions,
+            bytes: user_bytes,
+        };
+        Ok((guild, user))
+    }
+
+    // usage of today, of the whole guild if `user_id` is `None`
+    pub fn get_usage(&self, guild_id: GuildID, user_id: Option<UserID>) -> Result<Usage> {
+        let (executions, bytes): (Option<u64>, Option<u64>) = self
+            .conn
+            .lock()
+            .hget(self.usage_key(guild_id, user_id), &["executions", "bytes"])?;
+        Ok(Usage {
+            executions: executions.unwrap_or(0),
+            bytes: bytes.unwrap_or(0),
+        })
+    }
+
+    // newest first
+    pub(crate) fn get_audit_log(&self, guild_id: GuildID) -> Result<Vec<Record>> {
+        let key = Key::Audit {
+            prefix: &self.prefix,
+            guild_id,
+        };
+        let records = self.conn.lock().lrange(key, 0, -1)?;
+        Ok(records)
+    }
+
+    // audit records of mutations performed through this `Setting`
+    pub(crate) fn take_recorded(&self) -> Vec<Record> {
+        std::mem::take(&mut *self.recorded.lock())
     }
 }
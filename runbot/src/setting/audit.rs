@@ -0,0 +1,77 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::model::audit::AuditScope;
+use crate::model::user_id::UserID;
+
+use serde::{Deserialize, Serialize};
+
+// Raw audit record, stored as JSON in a Redis list.
+// Values are kept in their stored form, and are resolved to names on read.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct Record {
+    pub actor: UserID,
+    pub scope: AuditScope,
+    pub field: String,
+    pub hash_key: Option<String>,
+    pub old: Option<String>,
+    pub new: Option<String>,
+    pub timestamp: u64,
+}
+
+impl Record {
+    pub fn new(
+        actor: UserID,
+        scope: AuditScope,
+        field: &str,
+        hash_key: Option<String>,
+        old: Option<String>,
+        new: Option<String>,
+    ) -> Record {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        Record {
+            actor,
+            scope,
+            field: field.to_owned(),
+            hash_key,
+            old,
+            new,
+            timestamp,
+        }
+    }
+}
+
+impl redis::ToRedisArgs for Record {
+    fn write_redis_args<W: ?Sized>(&self, out: &mut W)
+    where
+        W: redis::RedisWrite,
+    {
+        serde_json::to_string(self)
+            .expect("failed to serialize audit record")
+            .write_redis_args(out)
+    }
+}
+
+impl redis::FromRedisValue for Record {
+    fn from_redis_value(v: &redis::Value) -> redis::RedisResult<Self> {
+        let s = String::from_redis_value(v)?;
+        serde_json::from_str(&s).map_err(|e| {
+            redis::RedisError::from((
+                redis::ErrorKind::TypeError,
+                "malformed audit record",
+                e.to_string(),
+            ))
+        })
+    }
+}
+
+// string representation of a value as it is stored in Redis
+pub(crate) fn stored_string<V: redis::ToRedisArgs>(value: &V) -> String {
+    value
+        .to_redis_args()
+        .into_iter()
+        .map(|arg| String::from_utf8_lossy(&arg).into_owned())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
@@ -0,0 +1,292 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::model::channel_id::ChannelID;
+use crate::model::compiler::CompilerID;
+use crate::model::guild_id::GuildID;
+use crate::model::language::LanguageID;
+use crate::model::user_id::UserID;
+use crate::Result;
+
+use super::Scope;
+
+use parking_lot::Mutex;
+
+// Settings of a channel with the guild defaults already applied
+#[derive(Debug, Clone)]
+pub(crate) struct ChannelSetting {
+    pub auto: bool,
+    pub auto_save: bool,
+    pub remap: HashMap<LanguageID, CompilerID>,
+    pub options: HashMap<CompilerID, String>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct UserSetting {
+    pub auto: Option<bool>,
+    pub auto_save: Option<bool>,
+    pub remap: HashMap<LanguageID, CompilerID>,
+    pub options: HashMap<CompilerID, String>,
+    pub locale: Option<String>,
+}
+
+// Guild-wide settings read on every message
+#[derive(Debug, Clone)]
+pub(crate) struct GuildSetting {
+    pub prefix: Option<String>,
+    pub locale: Option<String>,
+    pub access: Vec<String>,
+}
+
+struct Entry<T> {
+    value: Arc<T>,
+    fetched_at: Instant,
+}
+
+struct Entries<K, T>(HashMap<K, Entry<T>>);
+
+impl<K: Eq + Hash, T> Entries<K, T> {
+    fn get(&mut self, key: &K, ttl: Duration) -> Option<Arc<T>> {
+        match self.0.get(key) {
+            Some(entry) if entry.fetched_at.elapsed() < ttl => Some(Arc::clone(&entry.value)),
+            Some(_) => {
+                self.0.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    fn put(&mut self, key: K, value: T, ttl: Duration) -> Arc<T> {
+        self.0.retain(|_, e| e.fetched_at.elapsed() < ttl);
+
+        let value = Arc::new(value);
+        let entry = Entry {
+            value: Arc::clone(&value),
+            fetched_at: Instant::now(),
+        };
+        self.0.insert(key, entry);
+        value
+    }
+}
+
+struct Inner {
+    // bumped on every invalidation, so that values read before it are not cached
+    generation: u64,
+    channels: Entries<(GuildID, ChannelID), ChannelSetting>,
+    guilds: Entries<GuildID, GuildSetting>,
+    // keyed by `Scope::User` or `Scope::Direct`
+    users: Entries<Scope, UserSetting>,
+}
+
+// In-process cache of resolved settings, shared among `Context`s.
+// Writes through `Setting` invalidate affected entries; `watch` additionally
+// invalidates entries on writes from other processes via Redis keyspace notifications.
+#[derive(Clone)]
+pub struct SettingCache {
+    ttl: Duration,
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl SettingCache {
+    pub fn new(ttl: Duration) -> SettingCache {
+        let inner = Inner {
+            generation: 0,
+            channels: Entries(HashMap::new()),
+            guilds: Entries(HashMap::new()),
+            users: Entries(HashMap::new()),
+        };
+        SettingCache {
+            ttl,
+            inner: Arc::new(Mutex::new(inner)),
+        }
+    }
+
+    // A cache with zero TTL never hits
+    pub fn disabled() -> SettingCache {
+        SettingCache::new(Duration::from_secs(0))
+    }
+
+    // Taken before reading Redis, and passed to `put_*` along with the values read
+    pub(crate) fn generation(&self) -> u64 {
+        self.inner.lock().generation
+    }
+
+    pub(crate) fn get_channel(
+        &self,
+        guild_id: GuildID,
+        channel_id: ChannelID,
+    ) -> Option<Arc<ChannelSetting>> {
+        self.inner
+            .lock()
+            .channels
+            .get(&(guild_id, channel_id), self.ttl)
+    }
+
+    pub(crate) fn put_channel(
+        &self,
+        guild_id: GuildID,
+        channel_id: ChannelID,
+        setting: ChannelSetting,
+        generation: u64,
+    ) -> Arc<ChannelSetting> {
+        let mut inner = self.inner.lock();
+        if inner.generation != generation {
+            return Arc::new(setting);
+        }
+        inner
+            .channels
+            .put((guild_id, channel_id), setting, self.ttl)
+    }
+
+    pub(crate) fn get_guild(&self, guild_id: GuildID) -> Option<Arc<GuildSetting>> {
+        self.inner.lock().guilds.get(&guild_id, self.ttl)
+    }
+
+    pub(crate) fn put_guild(
+        &self,
+        guild_id: GuildID,
+        setting: GuildSetting,
+        generation: u64,
+    ) -> Arc<GuildSetting> {
+        let mut inner = self.inner.lock();
+        if inner.generation != generation {
+            return Arc::new(setting);
+        }
+        inner.guilds.put(guild_id, setting, self.ttl)
+    }
+
+    pub(crate) fn get_personal(&self, scope: Scope) -> Option<Arc<UserSetting>> {
+        self.inner.lock().users.get(&scope, self.ttl)
+    }
+
+    pub(crate) fn put_personal(
+        &self,
+        scope: Scope,
+        setting: UserSetting,
+        generation: u64,
+    ) -> Arc<UserSetting> {
+        let mut inner = self.inner.lock();
+        if inner.generation != generation {
+            return Arc::new(setting);
+        }
+        inner.users.put(scope, setting, self.ttl)
+    }
+
+    pub fn invalidate_channel(&self, guild_id: GuildID, channel_id: ChannelID) {
+        let mut inner = self.inner.lock();
+        inner.generation += 1;
+        inner.channels.0.remove(&(guild_id, channel_id));
+    }
+
+    pub fn invalidate_guild(&self, guild_id: GuildID) {
+        let mut inner = self.inner.lock();
+        inner.generation += 1;
+        inner.channels.0.retain(|(g, _), _| *g != guild_id);
+        inner.guilds.0.remove(&guild_id);
+    }
+
+    pub fn invalidate_user(&self, user_id: UserID) {
+        self.invalidate_personal(Scope::User(user_id));
+    }
+
+    pub fn invalidate_direct(&self, user_id: UserID) {
+        self.invalidate_personal(Scope::Direct(user_id));
+    }
+
+    pub(crate) fn invalidate_personal(&self, scope: Scope) {
+        let mut inner = self.inner.lock();
+        inner.generation += 1;
+        inner.users.0.remove(&scope);
+    }
+
+    pub fn clear(&self) {
+        let mut inner = self.inner.lock();
+        inner.generation += 1;
+        inner.channels.0.clear();
+        inner.guilds.0.clear();
+        inner.users.0.clear();
+    }
+
+    // Invalidates entries affected by a change of the given Redis key.
+    // This mirrors the key layout in `Key`.
+    fn invalidate_key(&self, prefix: &str, key: &str) {
+        let rest = match key.strip_prefix(prefix).and_then(|k| k.strip_prefix(':')) {
+            Some(rest) => rest,
+            None => return,
+        };
+        let parts: Vec<_> = rest.split(':').collect();
+        let parse = |s: &str| s.parse::<u64>().ok();
+
+        match parts[..] {
+            ["channel", guild_id, channel_id, _] => match (parse(guild_id), parse(channel_id)) {
+                (Some(g), Some(c)) => {
+                    self.invalidate_channel(GuildID::from_u64(g), ChannelID::from_u64(c))
+                }
+                _ => self.clear(),
+            },
+            ["guild", guild_id, _] => match parse(guild_id) {
+                Some(g) => self.invalidate_guild(GuildID::from_u64(g)),
+                None => self.clear(),
+            },
+            ["user", user_id, _] => match parse(user_id) {
+                Some(u) => self.invalidate_user(UserID::from_u64(u)),
+                None => self.clear(),
+            },
+            ["direct", user_id, _] => match parse(user_id) {
+                Some(u) => self.invalidate_direct(UserID::from_u64(u)),
+                None => self.clear(),
+            },
+            _ => {}
+        }
+    }
+
+    // Subscribes to keyspace notifications of keys under `prefix` and invalidates
+    // entries accordingly. This blocks forever, so run it in a dedicated thread.
+    // Redis must be configured with `notify-keyspace-events` including `K`, `$`, `h` and `g`.
+    pub fn watch(&self, client: &redis::Client, prefix: &str) -> Result<()> {
+        let mut conn = client.get_connection()?;
+        let mut pubsub = conn.as_pubsub();
+        pubsub.psubscribe(format!("__keyspace@*__:{}:*", prefix))?;
+
+        loop {
+            let msg = pubsub.get_message()?;
+            let channel = msg.get_channel_name();
+            if let Some(idx) = channel.find("__:") {
+                self.invalidate_key(prefix, &channel[idx + 3..]);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setting() -> ChannelSetting {
+        ChannelSetting {
+            auto: true,
+            auto_save: false,
+            remap: HashMap::new(),
+            options: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn value_read_before_invalidation_is_not_cached() {
+        let cache = SettingCache::new(Duration::from_secs(60));
+        let guild_id = GuildID::from_u64(1);
+        let channel_id = ChannelID::from_u64(2);
+
+        let generation = cache.generation();
+        cache.invalidate_channel(guild_id, channel_id);
+        cache.put_channel(guild_id, channel_id, setting(), generation);
+        assert!(cache.get_channel(guild_id, channel_id).is_none());
+
+        let generation = cache.generation();
+        cache.put_channel(guild_id, channel_id, setting(), generation);
+        assert!(cache.get_channel(guild_id, channel_id).is_some());
+    }
+}
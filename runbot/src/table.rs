@@ -35,7 +35,47 @@ impl Table {
         self.languages.values().find(|c| c.is_named_as(name))
     }
 
+    pub fn find_language_by_extension(&self, extension: &LanguageName) -> Option<&Language> {
+        self.languages.values().find(|c| c.has_extension(extension))
+    }
+
+    // A shebang decides the language. Otherwise the language with the most keywords found wins,
+    // but only if there is no tie.
+    pub fn guess_language(&self, text: &str) -> Option<&Language> {
+        if let Some(interpreter) = shebang_interpreter(text) {
+            return self
+                .languages
+                .values()
+                .find(|l| l.is_interpreted_by(interpreter));
+        }
+
+        let mut scores: Vec<_> = self
+            .languages
+            .values()
+            .map(|l| (l.keyword_score(text), l))
+            .filter(|(score, _)| *score > 0)
+            .collect();
+        scores.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        match &scores[..] {
+            [(_, l)] => Some(l),
+            [(s1, l), (s2, _), ..] if s1 > s2 => Some(l),
+            _ => None,
+        }
+    }
+
     pub fn list_languages(&self) -> impl Iterator<Item = &Language> {
         self.languages.values()
     }
 }
+
+// `python` for `#!/usr/bin/python3`, `#!/usr/bin/env python3.8` and so on
+fn shebang_interpreter(text: &str) -> Option<&str> {
+    let line = text.trim_start().lines().next()?.strip_prefix("#!")?;
+    let mut words = line.split_whitespace();
+    let mut command = words.next()?;
+    if command.ends_with("/env") || command == "env" {
+        command = words.find(|w| !w.starts_with('-'))?;
+    }
+    let name = command.rsplit('/').next()?;
+    Some(name.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.'))
+}
@@ -0,0 +1,116 @@
+// Helpers for tests against a Redis server, given by `RUNBOT_TEST_REDIS_URI`.
+// Those tests are ignored by default; run them with `cargo test -- --ignored`.
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::limiter::{Limiter, Limits};
+use crate::model::channel_id::ChannelID;
+use crate::model::compiler::{Compiler, CompilerID, CompilerName};
+use crate::model::guild_id::GuildID;
+use crate::model::language::{Heuristics, Language, LanguageID, LanguageName};
+use crate::model::user_id::UserID;
+use crate::setting::SettingCache;
+use crate::table::Table;
+use crate::Context;
+
+use parking_lot::Mutex;
+use redis::Commands;
+
+pub fn connect() -> redis::Connection {
+    let uri = env::var("RUNBOT_TEST_REDIS_URI").unwrap_or_else(|_| "redis://127.0.0.1/".into());
+    redis::Client::open(uri)
+        .and_then(|c| c.get_connection())
+        .expect("unable to connect to the test Redis server")
+}
+
+// A key prefix of its own, whose keys are deleted when dropped
+pub struct Namespace {
+    pub conn: Arc<Mutex<redis::Connection>>,
+    pub prefix: String,
+}
+
+impl Namespace {
+    pub fn new() -> Namespace {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let prefix = format!(
+            "runbot-test:{}:{}",
+            std::process::id(),
+            COUNT.fetch_add(1, Ordering::SeqCst)
+        );
+        Namespace {
+            conn: Arc::new(Mutex::new(connect())),
+            prefix,
+        }
+    }
+
+    pub fn keys(&self) -> Vec<String> {
+        let pattern = format!("{}:*", self.prefix);
+        let mut keys: Vec<String> = self.conn.lock().keys(pattern).unwrap();
+        keys.sort();
+        keys
+    }
+
+    pub fn context(&self, guild_id: Option<GuildID>, channel_id: u64, user_id: u64) -> Context {
+        Context::new(
+            guild_id,
+            ChannelID::from_u64(channel_id),
+            UserID::from_u64(user_id),
+            wandbox::blocking::Client::new("http://localhost/").unwrap(),
+            Arc::clone(&self.conn),
+            self.prefix.clone(),
+            SettingCache::new(Duration::from_secs(0)),
+            Limiter::new(Limits::default()),
+            table(),
+        )
+    }
+}
+
+impl Drop for Namespace {
+    fn drop(&mut self) {
+        let keys = self.keys();
+        if !keys.is_empty() {
+            let _: redis::RedisResult<()> = self.conn.lock().del(keys);
+        }
+    }
+}
+
+// `cpp` defaults to `gcc-head` and can be remapped to `clang-head`
+pub fn table() -> Table {
+    let cpp = LanguageID::from_u64(1);
+    let gcc = CompilerID::from_u64(1);
+    let clang = CompilerID::from_u64(2);
+
+    let compiler = |id, name: &str| {
+        Compiler::new(
+            id,
+            CompilerName::from_string(name.to_string()),
+            None,
+            cpp,
+            name.to_string(),
+        )
+    };
+    let mut compilers = HashMap::new();
+    compilers.insert(gcc, compiler(gcc, "gcc-head"));
+    compilers.insert(clang, compiler(clang, "clang-head"));
+
+    let mut languages = HashMap::new();
+    languages.insert(
+        cpp,
+        Language::new(
+            cpp,
+            LanguageName::from_string("cpp".to_string()),
+            HashSet::new(),
+            HashSet::new(),
+            Heuristics::default(),
+            Some(gcc),
+        ),
+    );
+
+    Table {
+        compilers,
+        languages,
+    }
+}